@@ -5,6 +5,21 @@ use clap::Shell;
 use std::env;
 use std::path::PathBuf;
 
+#[path = "src/color.rs"]
+#[allow(dead_code)]
+mod color;
+#[path = "src/cursor.rs"]
+#[allow(dead_code)]
+mod cursor;
+#[path = "src/fields.rs"]
+#[allow(dead_code)]
+mod fields;
+#[path = "src/layout.rs"]
+#[allow(dead_code)]
+mod layout;
+#[path = "src/profile.rs"]
+mod profile;
+
 include!("src/config.rs");
 
 fn main() {
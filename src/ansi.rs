@@ -0,0 +1,106 @@
+// An SGR sequence that was stripped out of a line, and the byte offset in the
+// stripped text it applied from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub offset: usize,
+    pub sequence: String,
+}
+
+// Whether an SGR sequence starts by resetting every attribute, so the ones
+// before it no longer apply.
+pub fn resets(sequence: &str) -> bool {
+    let params = &sequence[2..sequence.len() - 1];
+
+    matches!(params.split(';').next(), Some("" | "0" | "00"))
+}
+
+// Removes escape sequences from the text, keeping the SGR ones that color it.
+pub fn strip(text: &str) -> (String, Vec<Style>) {
+    let bytes = text.as_bytes();
+    let mut stripped = String::with_capacity(text.len());
+    let mut styles = vec![];
+    let mut plain_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != 0x1B {
+            i += 1;
+            continue;
+        }
+
+        stripped.push_str(&text[plain_start..i]);
+        let end = sequence_end(bytes, i);
+        if bytes.get(i + 1) == Some(&b'[') && bytes[end - 1] == b'm' {
+            styles.push(Style { offset: stripped.len(), sequence: text[i..end].to_string() });
+        }
+        i = end;
+        plain_start = end;
+    }
+    stripped.push_str(&text[plain_start..]);
+
+    (stripped, styles)
+}
+
+// Where the escape sequence starting at `start` ends: after the final byte of
+// a CSI sequence, after the terminator of an OSC one and otherwise after the
+// first byte following the escape that isn't an intermediate one.
+fn sequence_end(bytes: &[u8], start: usize) -> usize {
+    match bytes.get(start + 1) {
+        Some(b'[') => bytes[start + 2..]
+            .iter()
+            .position(|byte| (0x40..=0x7E).contains(byte))
+            .map_or(bytes.len(), |i| start + 2 + i + 1),
+        Some(b']') => {
+            let mut i = start + 2;
+            while i < bytes.len() {
+                match bytes[i] {
+                    0x07 => return i + 1,
+                    0x1B if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                    _ => i += 1,
+                }
+            }
+            bytes.len()
+        }
+        Some(byte) if byte.is_ascii() => bytes[start + 1..]
+            .iter()
+            .position(|byte| !(0x20..=0x2F).contains(byte))
+            .map_or(bytes.len(), |i| start + 1 + i + 1),
+        _ => start + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip() {
+        let (text, styles) = strip("\x1B[31mred\x1B[0m plain \x1B[1;32mgreen\x1B[m");
+
+        assert_eq!("red plain green", text);
+        assert_eq!(
+            vec![
+                Style { offset: 0, sequence: "\x1B[31m".to_string() },
+                Style { offset: 3, sequence: "\x1B[0m".to_string() },
+                Style { offset: 10, sequence: "\x1B[1;32m".to_string() },
+                Style { offset: 15, sequence: "\x1B[m".to_string() },
+            ],
+            styles
+        );
+    }
+
+    #[test]
+    fn test_strip_other_sequences() {
+        let (text, styles) = strip("a\x1B[Kb\x1B]8;;http://x\x07c\x1B(Bd é\x1B");
+
+        assert_eq!("abcd é", text);
+        assert!(styles.is_empty());
+    }
+
+    #[test]
+    fn test_resets() {
+        assert!(resets("\x1B[0;31m"));
+        assert!(resets("\x1B[m"));
+        assert!(!resets("\x1B[38;5;0m"));
+    }
+}
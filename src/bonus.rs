@@ -1,24 +1,26 @@
-pub const SLASH: f32 = 0.9;
-pub const WORD: f32 = 0.87;
-pub const DOT: f32 = 0.6;
-pub const CAPITAL: f32 = 0.68;
-pub const FILENAME: f32 = 0.1;
-
-pub fn compute(choice_chars: &[char]) -> Vec<f32> {
-    let mut last_char = '/';
-    let last_section = choice_chars.iter().filter(|&n| *n == '/').count();
+use crate::profile::ScoringProfile;
+
+pub fn compute(choice_chars: &[char], profile: &ScoringProfile) -> Vec<f32> {
+    // Nothing comes before the first char, which gets the start bonus instead.
+    let mut last_char = '\0';
+    let last_section = choice_chars.iter().filter(|&n| is_separator(*n)).count();
     let mut section = 0;
 
     choice_chars
         .iter()
-        .map(|&cchar| {
-            let mut bonus = for_char(last_char, cchar);
+        .enumerate()
+        .map(|(i, &cchar)| {
+            let mut bonus = if i == 0 && cchar.is_alphanumeric() {
+                profile.start
+            } else {
+                for_char(last_char, cchar, choice_chars.get(i + 1), profile)
+            };
             last_char = cchar;
 
-            if cchar == '/' {
+            if is_separator(cchar) {
                 section += 1;
             } else if section == last_section {
-                bonus += FILENAME;
+                bonus += profile.filename;
             }
 
             bonus
@@ -26,22 +28,36 @@ pub fn compute(choice_chars: &[char]) -> Vec<f32> {
         .collect()
 }
 
-fn for_char(prev: char, current: char) -> f32 {
-    match current {
-        'a'..='z' | '0'..='9' => for_previous(prev),
-        'A'..='Z' => match prev {
-            'a'..='z' => CAPITAL,
-            _ => for_previous(prev),
-        },
-        _ => 0.0,
+fn is_separator(ch: char) -> bool {
+    ch == '/' || ch == '\\'
+}
+
+fn for_char(prev: char, current: char, next: Option<&char>, profile: &ScoringProfile) -> f32 {
+    if current.is_uppercase() {
+        if prev.is_lowercase() || prev.is_numeric() {
+            profile.capital
+        } else if prev.is_uppercase() && next.is_some_and(|n| n.is_lowercase()) {
+            // The last capital of an acronym starts the next word: HTTP|Server
+            profile.capital
+        } else {
+            for_previous(prev, profile)
+        }
+    } else if current.is_alphabetic() && prev.is_numeric() {
+        profile.capital
+    } else if current.is_alphanumeric() {
+        for_previous(prev, profile)
+    } else {
+        0.0
     }
 }
 
-fn for_previous(ch: char) -> f32 {
+fn for_previous(ch: char, profile: &ScoringProfile) -> f32 {
     match ch {
-        '/' => SLASH,
-        '-' | '_' | ' ' => WORD,
-        '.' => DOT,
+        '/' | '\\' => profile.slash,
+        '-' | '_' | ' ' | '@' | '#' | '(' | '[' => profile.word,
+        '.' => profile.dot,
+        // A word boundary in every scheme, which a profile can raise.
+        ':' => profile.word.max(profile.colon),
         _ => 0.0,
     }
 }
@@ -54,14 +70,14 @@ mod tests {
     #[test]
     fn test_compute_dir() {
         assert_eq!(
-            compute(&"a/b/c/d".chars().collect::<Vec<char>>()),
+            compute(&"a/b/c/d".chars().collect::<Vec<char>>(), &ScoringProfile::PATH),
             vec![0.9, 0.0, 0.9, 0.0, 0.9, 0.0, 1.0]
         );
     }
 
     #[test]
     fn test_compute_capital() {
-        let bonuses = compute(&"aTestString".chars().collect::<Vec<char>>());
+        let bonuses = compute(&"aTestString".chars().collect::<Vec<char>>(), &ScoringProfile::PATH);
 
         assert_eq!(1.0, bonuses[0]);
         approx_eq!(f32, 0.78, bonuses[1], epsilon = 0.001);
@@ -75,4 +91,69 @@ mod tests {
         assert_eq!(0.1, bonuses[9]);
         assert_eq!(0.1, bonuses[10]);
     }
+
+    #[test]
+    fn test_compute_plain() {
+        assert_eq!(
+            compute(&"a/b c".chars().collect::<Vec<char>>(), &ScoringProfile::PLAIN),
+            vec![0.9, 0.0, 0.0, 0.0, 0.87]
+        );
+    }
+
+    #[test]
+    fn test_compute_start() {
+        let profile = ScoringProfile { slash: 0.0, filename: 0.0, ..ScoringProfile::PATH };
+
+        assert_eq!(0.9, compute(&['a'], &profile)[0]);
+        assert_eq!(0.0, compute(&['-', 'a'], &profile)[0]);
+    }
+
+    #[test]
+    fn test_compute_code() {
+        assert_eq!(
+            compute(&"io::readLine".chars().collect::<Vec<char>>(), &ScoringProfile::CODE),
+            vec![0.9, 0.0, 0.0, 0.0, 0.9, 0.0, 0.0, 0.0, 0.87, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_compute_boundaries() {
+        let bonuses = compute(&"a:b@c#d(e[f".chars().collect::<Vec<char>>(), &ScoringProfile::PATH);
+
+        assert_eq!(
+            vec![1.0, 0.1, 0.97, 0.1, 0.97, 0.1, 0.97, 0.1, 0.97, 0.1, 0.97],
+            bonuses
+        );
+    }
+
+    #[test]
+    fn test_compute_digit_to_letter() {
+        let bonuses = compute(&"v2beta".chars().collect::<Vec<char>>(), &ScoringProfile::PLAIN);
+
+        assert_eq!(vec![0.9, 0.0, 0.68, 0.0, 0.0, 0.0], bonuses);
+    }
+
+    #[test]
+    fn test_compute_acronym() {
+        let bonuses = compute(&"HTTPServer".chars().collect::<Vec<char>>(), &ScoringProfile::PLAIN);
+
+        assert_eq!(vec![0.9, 0.0, 0.0, 0.0, 0.68, 0.0, 0.0, 0.0, 0.0, 0.0], bonuses);
+    }
+
+    #[test]
+    fn test_compute_unicode() {
+        let bonuses = compute(&"über_ärger".chars().collect::<Vec<char>>(), &ScoringProfile::PLAIN);
+
+        assert_eq!(0.9, bonuses[0]);
+        assert_eq!(0.87, bonuses[5]);
+        assert_eq!(0.0, bonuses[6]);
+    }
+
+    #[test]
+    fn test_compute_windows_path() {
+        assert_eq!(
+            compute(&"a\\b\\c".chars().collect::<Vec<char>>(), &ScoringProfile::PATH),
+            vec![0.9, 0.0, 0.9, 0.0, 1.0]
+        );
+    }
 }
@@ -1,44 +1,366 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use regex::Regex;
+use crate::ansi::{self, Style};
 use crate::bonus;
 use crate::config::Config;
+use crate::fields::{self, FieldRange};
 
 pub struct Choice<'a> {
-    pub searchable:  &'a str,
+    pub searchable: Cow<'a, str>,
     pub lower_searchable:  Vec<char>,
     pub searchable_len: usize,
-    pub returnable: &'a str,
+    pub display: Cow<'a, str>,
+    pub returnable: Cow<'a, str>,
     pub bonus: Vec<f32>,
+    pub approximate: bool,
+    pub ascii: bool,
+    content: Cow<'a, str>,
+    search_spans: Vec<Range<usize>>,
+    display_spans: Vec<Range<usize>>,
+    styles: Vec<Style>,
+}
+
+const RECORD_SEPARATOR: &str = " | ";
+
+// The text of a field and where it ends once its trailing delimiter is included.
+struct Field {
+    text: Range<usize>,
+    end: usize,
 }
 
 impl<'a> Choice<'a> {
     pub fn new(content: &'a str, config: &Config) -> Choice<'a> {
-        match &config.delimiter {
-            Some(delimiter_regex) => {
-                let split_content: Vec<&str> = delimiter_regex.split(content).collect();
+        let (content, styles) = strip(Cow::Borrowed(content), config);
+        let fields = config.delimiter.as_ref().map(|delimiter_regex| split(&content, delimiter_regex));
+
+        Self::with_fields(content, styles, fields, &[], None, config)
+    }
+
+    pub fn from_owned(content: String, config: &Config) -> Choice<'a> {
+        let (content, styles) = strip(Cow::Owned(content), config);
+        let fields = config.delimiter.as_ref().map(|delimiter_regex| split(&content, delimiter_regex));
+
+        Self::with_fields(content, styles, fields, &[], None, config)
+    }
+
+    // Builds a choice from an already parsed record, joining its fields with
+    // RECORD_SEPARATOR so they read as columns. What gets printed joins them
+    // with `delimiter` instead, the way they were read.
+    pub fn from_record(record: &[String], header: &[String], delimiter: &str, config: &Config) -> Choice<'a> {
+        let output = record.join(delimiter);
+        let output_fields = record_fields(record, delimiter);
+
+        // Quoted fields can span lines, which would break drawing a row per
+        // choice, so they're only kept in what gets printed.
+        let content = record.join(RECORD_SEPARATOR).replace(['\r', '\n'], " ");
+        let fields = record_fields(record, RECORD_SEPARATOR);
+
+        Self::with_fields(Cow::Owned(content), vec![], Some(fields), header, Some((&output, &output_fields)), config)
+    }
 
-                let searchable = split_content[config.field.unwrap()];
-                let returnable = match config.output {
-                    Some(o) => split_content[o],
-                    None => searchable
-                };
+    fn with_fields(
+        content: Cow<'a, str>,
+        styles: Vec<Style>,
+        fields: Option<Vec<Field>>,
+        header: &[String],
+        output: Option<(&str, &[Field])>,
+        config: &Config,
+    ) -> Choice<'a> {
+        let whole = |text: &str| vec![Range { start: 0, end: text.len() }];
+        let select = |fields: &[Field], ranges: &Option<Vec<FieldRange>>, default: &[Range<usize>]| match ranges {
+            Some(ranges) => join(fields, &fields::select(ranges, fields.len(), header)),
+            None => default.to_vec(),
+        };
 
-                let (bonus, searchable_len, lower_searchable) = parse_searchable(searchable);
+        let (search_spans, display_spans, output_spans) = match fields {
+            Some(fields) => {
+                let search_spans = select(&fields, &config.search_fields, &whole(&content));
+                let display_spans = select(&fields, &config.display_fields, &search_spans);
+                let output_spans = select(&fields, &config.output_fields, &search_spans);
 
-                Choice { searchable, returnable, bonus, searchable_len, lower_searchable }
+                (search_spans, display_spans, output_spans)
             },
-            None => {
-                let (bonus, searchable_len, lower_searchable) = parse_searchable(content);
+            None => (whole(&content), whole(&content), whole(&content))
+        };
 
-                Choice { searchable: content, returnable: content, bonus, searchable_len, lower_searchable }
+        let searchable = extract(&content, &search_spans);
+        let (bonus, searchable_len, lower_searchable, ascii) = parse_searchable(&searchable, config);
+        let approximate = searchable_len > config.max_length;
+
+        let returnable = if let Some((output, fields)) = output {
+            let search_spans = select(fields, &config.search_fields, &whole(output));
+            let output_spans = select(fields, &config.output_fields, &search_spans);
+            Cow::Owned(extract(&Cow::Borrowed(output), &output_spans).into_owned())
+        } else if config.ansi_output && !styles.is_empty() {
+            Cow::Owned(restyle(&content, &output_spans, &styles))
+        } else {
+            extract(&content, &output_spans)
+        };
+
+        Choice {
+            display: extract(&content, &display_spans),
+            returnable,
+            searchable, lower_searchable, searchable_len, bonus, approximate, ascii,
+            content, search_spans, display_spans, styles,
+        }
+    }
+
+    // The styles that were stripped from the displayed text, each with the
+    // index of the displayed character it applies from.
+    pub fn display_styles(&self) -> Vec<(usize, &str)> {
+        if self.styles.is_empty() {
+            return vec![];
+        }
+
+        let mut styles = self.styles.iter().peekable();
+        let mut display_styles = vec![];
+        for (i, offset) in self.char_offsets(&self.display_spans).into_iter().enumerate() {
+            while let Some(style) = styles.next_if(|style| style.offset <= offset) {
+                display_styles.push((i, style.sequence.as_str()));
             }
         }
+
+        display_styles
     }
 
+    // Maps positions in the searchable text onto the displayed text, dropping
+    // any that fall in fields that aren't displayed.
+    pub fn display_positions(&self, positions: &[usize]) -> Vec<usize> {
+        if self.search_spans == self.display_spans {
+            return positions.to_vec();
+        }
+
+        let search_offsets = self.char_offsets(&self.search_spans);
+        let matched: Vec<usize> = positions.iter().filter_map(|&i| search_offsets.get(i).copied()).collect();
+
+        self.char_offsets(&self.display_spans)
+            .iter()
+            .enumerate()
+            .filter(|(_, offset)| matched.contains(offset))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn char_offsets(&self, spans: &[Range<usize>]) -> Vec<usize> {
+        spans
+            .iter()
+            .flat_map(|span| {
+                self.content[span.clone()].char_indices().map(move |(i, _)| span.start + i)
+            })
+            .collect()
+    }
+}
+
+fn split(content: &str, delimiter_regex: &Regex) -> Vec<Field> {
+    let mut fields = vec![];
+    let mut start = 0;
+
+    for mat in delimiter_regex.find_iter(content) {
+        if mat.end() > start {
+            fields.push(Field { text: start..mat.start(), end: mat.end() });
+            start = mat.end();
+        }
+    }
+    fields.push(Field { text: start..content.len(), end: content.len() });
+
+    fields
+}
+
+// The fields of a record once joined with `separator`.
+fn record_fields(record: &[String], separator: &str) -> Vec<Field> {
+    let mut fields = vec![];
+    let mut start = 0;
+
+    for (i, field) in record.iter().enumerate() {
+        let end = start + field.len();
+        let separator = if i + 1 < record.len() { separator.len() } else { 0 };
+        fields.push(Field { text: start..end, end: end + separator });
+        start = end + separator;
+    }
+
+    fields
 }
 
-fn parse_searchable(searchable: &str) -> (Vec<f32>, usize, Vec<char>) {
-    let bonus = bonus::compute(&searchable.chars().collect::<Vec<char>>());
+// Runs of neighbouring fields become a single span that keeps the delimiters
+// between them. The delimiter after the last field is dropped.
+fn join(fields: &[Field], selected: &[usize]) -> Vec<Range<usize>> {
+    let mut spans: Vec<Range<usize>> = vec![];
+
+    selected.iter().for_each(|&i| match spans.last_mut() {
+        Some(last) if last.end == fields[i].text.start => last.end = fields[i].end,
+        _ => spans.push(fields[i].text.start..fields[i].end),
+    });
+    if let (Some(last), Some(&i)) = (spans.last_mut(), selected.last()) {
+        last.end = fields[i].text.end;
+    }
+
+    spans
+}
+
+// Borrowed content yields borrowed text where possible, owned content always
+// has to be copied out.
+fn extract<'a>(content: &Cow<'a, str>, spans: &[Range<usize>]) -> Cow<'a, str> {
+    let content = match content {
+        Cow::Borrowed(content) => *content,
+        Cow::Owned(content) => return Cow::Owned(spans.iter().map(|span| &content[span.clone()]).collect()),
+    };
+
+    match spans {
+        [] => Cow::Borrowed(""),
+        [span] => Cow::Borrowed(&content[span.clone()]),
+        _ => Cow::Owned(spans.iter().map(|span| &content[span.clone()]).collect()),
+    }
+}
+
+// Strips escape sequences when asked to, leaving text without any untouched.
+fn strip<'a>(content: Cow<'a, str>, config: &Config) -> (Cow<'a, str>, Vec<Style>) {
+    if !config.ansi || !content.contains('\x1B') {
+        return (content, vec![]);
+    }
+
+    let (stripped, styles) = ansi::strip(&content);
+    (Cow::Owned(stripped), styles)
+}
+
+// Extracts the spans with the styles that were stripped from them put back,
+// styles from before a span applying from its start.
+fn restyle(content: &str, spans: &[Range<usize>], styles: &[Style]) -> String {
+    let mut styled = String::new();
+    let mut styles = styles.iter().peekable();
+
+    for span in spans {
+        let mut position = span.start;
+        while let Some(style) = styles.next_if(|style| style.offset <= span.end) {
+            let offset = style.offset.max(position);
+            styled.push_str(&content[position..offset]);
+            styled.push_str(&style.sequence);
+            position = offset;
+        }
+        styled.push_str(&content[position..span.end]);
+    }
+
+    styled
+}
+
+fn parse_searchable(searchable: &str, config: &Config) -> (Vec<f32>, usize, Vec<char>, bool) {
+    let bonus = bonus::compute(&searchable.chars().collect::<Vec<char>>(), &config.profile);
     let searchable_len = searchable.chars().count();
     let lower_searchable: Vec<char> = searchable.chars().map(|cchar| cchar.to_ascii_lowercase()).collect();
+    let ascii = searchable.is_ascii();
+
+    (bonus, searchable_len, lower_searchable, ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Format;
+
+    #[test]
+    fn test_new() {
+        let choice = Choice::new("app/models/order.rb", &Config::default());
+
+        assert_eq!("app/models/order.rb", choice.searchable);
+        assert_eq!("app/models/order.rb", choice.display);
+        assert_eq!("app/models/order.rb", choice.returnable);
+    }
+
+    #[test]
+    fn test_from_owned() {
+        let choice = Choice::from_owned("src/main.rs".to_string(), &Config::default());
+
+        assert_eq!("src/main.rs", choice.searchable);
+        assert_eq!("src/main.rs", choice.returnable);
+    }
+
+    #[test]
+    fn test_split_fields() {
+        let config = split_config(Some("1"), None, Some("0"));
+        let choice = Choice::new("12:app/models/order.rb:class Order", &config);
+
+        assert_eq!("app/models/order.rb", choice.searchable);
+        assert_eq!("app/models/order.rb", choice.display);
+        assert_eq!("12", choice.returnable);
+    }
+
+    #[test]
+    fn test_split_field_lists() {
+        let config = split_config(Some("1..2"), Some("0,2"), Some("-1"));
+        let choice = Choice::new("a:b:c:d", &config);
+
+        assert_eq!("b:c", choice.searchable);
+        assert_eq!("a:c", choice.display);
+        assert_eq!("d", choice.returnable);
+    }
+
+    #[test]
+    fn test_split_missing_fields() {
+        let config = split_config(Some("3"), None, Some("1"));
+        let choice = Choice::new("a:b", &config);
+
+        assert_eq!("", choice.searchable);
+        assert_eq!("", choice.display);
+        assert_eq!("b", choice.returnable);
+    }
+
+    #[test]
+    fn test_display_positions() {
+        let config = split_config(Some("1..2"), Some("0,2"), None);
+        let choice = Choice::new("a:b:c:d", &config);
+
+        assert_eq!(vec![2], choice.display_positions(&[0, 1, 2]));
+        assert_eq!(vec![0, 2], Choice::new("abc", &Config::default()).display_positions(&[0, 2]));
+    }
+
+    #[test]
+    fn test_from_record() {
+        let header = vec!["id".to_string(), "name".to_string(), "email".to_string()];
+        let record = vec!["7".to_string(), "Smith, Jane".to_string(), "jane@example.com".to_string()];
+        let config = Config {
+            format: Some(Format::Csv),
+            search_fields: Some(fields::parse("name,email").unwrap()),
+            output_fields: Some(fields::parse("name").unwrap()),
+            ..Config::default()
+        };
+        let choice = Choice::from_record(&record, &header, ",", &config);
 
-    (bonus, searchable_len, lower_searchable)
+        assert_eq!("Smith, Jane | jane@example.com", choice.searchable);
+        assert_eq!("Smith, Jane | jane@example.com", choice.display);
+        assert_eq!("Smith, Jane", choice.returnable);
+
+        let choice = Choice::from_record(&record, &header, "\t", &Config::default());
+        assert_eq!("7 | Smith, Jane | jane@example.com", choice.display);
+        assert_eq!("7\tSmith, Jane\tjane@example.com", choice.returnable);
+    }
+
+    #[test]
+    fn test_ansi() {
+        let config = Config { ansi: true, ..Config::default() };
+        let choice = Choice::new("\x1B[32mapp/\x1B[0morder.rb", &config);
+
+        assert_eq!("app/order.rb", choice.searchable);
+        assert_eq!("app/order.rb", choice.returnable);
+        assert_eq!(vec![(0, "\x1B[32m"), (4, "\x1B[0m")], choice.display_styles());
+    }
+
+    #[test]
+    fn test_ansi_output() {
+        let config = Config { ansi: true, ansi_output: true, ..split_config(Some("1"), None, Some("1")) };
+        let choice = Choice::new("\x1B[35m12\x1B[0m:\x1B[1morder\x1B[0m", &config);
+
+        assert_eq!("order", choice.searchable);
+        assert_eq!("\x1B[35m\x1B[0m\x1B[1morder\x1B[0m", choice.returnable);
+        assert_eq!(vec![(0, "\x1B[35m"), (0, "\x1B[0m"), (0, "\x1B[1m")], choice.display_styles());
+    }
+
+    fn split_config(search: Option<&str>, display: Option<&str>, output: Option<&str>) -> Config {
+        Config {
+            delimiter: Some(Regex::new(":").unwrap()),
+            search_fields: search.map(|spec| fields::parse(spec).unwrap()),
+            display_fields: display.map(|spec| fields::parse(spec).unwrap()),
+            output_fields: output.map(|spec| fields::parse(spec).unwrap()),
+            ..Config::default()
+        }
+    }
 }
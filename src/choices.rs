@@ -1,73 +1,285 @@
 use rayon::prelude::*;
 use crate::choice::Choice;
+use crate::color::{self, Theme};
+use crate::config::Config;
 use crate::cursor;
-use crate::matcher::Match;
+use crate::layout::Layout;
+use crate::matcher::{DrawOptions, Match, Mode, Pattern};
+use crate::profile::ScoringProfile;
 use crate::terminal::Terminal;
+use std::time::{Duration, Instant};
 
 pub struct Choices<'a> {
-    choices: &'a [Choice<'a>],
+    choices: Vec<Choice<'a>>,
     selected: usize,
     max_choices: usize,
-    matches: Vec<Match<'a>>,
-    show_scores: bool,
+    matches: Vec<Match>,
+    query: Vec<char>,
+    // What was selected before the choices were replaced, selected again
+    // once it turns up.
+    keep: Option<String>,
+    // Indexes of the marked choices, in the order they were marked.
+    marked: Vec<usize>,
+    draw_options: DrawOptions,
+    theme: Theme,
+    profile: ScoringProfile,
+    mode: Mode,
+    error: Option<String>,
+    typos: usize,
+    header: Vec<String>,
+    // The first input lines, shown with the header instead of being matched.
+    header_lines: usize,
+    header_choices: Vec<Choice<'a>>,
+    skipped: usize,
+    loading: bool,
+    spinner: usize,
+    layout: Layout,
+    cycle: bool,
+    // Whether the rows are drawn with labels to jump to them by.
+    jumping: bool,
+    // The choice clicked last and when, to tell a double click.
+    last_click: Option<(usize, Instant)>,
 }
 
 impl<'a> Choices<'a> {
     const OFFSET: usize = 1;
-
-    pub fn new(max_choices: usize, choices: &'a [Choice], show_scores: bool) -> Choices<'a> {
-        Choices {
+    // A label and the space after it.
+    const LABEL_WIDTH: usize = 2;
+    const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+    const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    // Home row keys first, rows past the last label can't be jumped to.
+    const JUMP_LABELS: [char; 26] = [
+        'a', 's', 'd', 'f', 'g', 'h', 'j', 'k', 'l', 'q', 'w', 'e', 'r',
+        't', 'y', 'u', 'i', 'o', 'p', 'z', 'x', 'c', 'v', 'b', 'n', 'm',
+    ];
+
+    pub fn new(config: &Config, choices: Vec<Choice<'a>>) -> Choices<'a> {
+        let mut new = Choices {
             selected: 0,
             matches: vec![],
-            choices,
-            max_choices,
-            show_scores,
-        }
+            query: vec![],
+            keep: None,
+            marked: vec![],
+            choices: vec![],
+            max_choices: config.lines,
+            draw_options: DrawOptions { show_scores: config.show_scores, keep_right: config.keep_right, ..DrawOptions::default() },
+            theme: config.color.clone(),
+            profile: config.profile,
+            mode: if config.regex {
+                Mode::Regex
+            } else if config.exact {
+                Mode::Exact
+            } else {
+                Mode::Fuzzy
+            },
+            error: None,
+            typos: config.typos,
+            header: config.header.clone(),
+            header_lines: config.header_lines,
+            header_choices: vec![],
+            skipped: 0,
+            loading: false,
+            spinner: 0,
+            layout: config.layout,
+            cycle: config.cycle,
+            jumping: false,
+            last_click: None,
+        };
+        new.choices = new.split_header(choices);
+
+        new
+    }
+
+    // Lines shown above the choices that can't be selected or filtered.
+    pub fn add_header(&mut self, header: Vec<String>) {
+        self.header.extend(header);
+    }
+
+    // Whether choices are still streaming in, shown as a spinner.
+    pub fn set_loading(&mut self, loading: bool) {
+        self.loading = loading;
+    }
+
+    pub fn tick(&mut self) -> String {
+        self.spinner += 1;
+        self.draw()
+    }
+
+    // Input lines that couldn't be parsed, reported in the status line.
+    pub fn set_skipped(&mut self, skipped: usize) {
+        self.skipped = skipped;
     }
 
     pub fn initial_draw(&mut self, terminal: &mut Terminal) {
         self.filter_choices(&[]);
+        self.redraw(terminal);
+    }
+
+    // Draws the choices from scratch starting at the current line, such as after
+    // another program had the terminal.
+    pub fn redraw(&self, terminal: &mut Terminal) {
+        // Room is made for every line up front, choices streamed in later would
+        // otherwise scroll the terminal out from under the saved position.
+        terminal.print(&self.layout.initial_draw(&self.lines(), self.reserved()));
+    }
+
+    // Moves the selection up or down on screen, which is towards worse matches
+    // when the layout draws the best match last.
+    pub fn up(&mut self) -> String {
+        if self.layout.best_last() { self.next() } else { self.previous() }
+    }
 
-        terminal.print(&format!(
-            "\r\n{}{}\r",
-            self.draw_choices(),
-            cursor::up(self.max_choices())
-        ));
+    pub fn down(&mut self) -> String {
+        if self.layout.best_last() { self.previous() } else { self.next() }
+    }
+
+    pub fn page_up(&mut self) -> String {
+        let page = self.max_choices as isize;
+        self.move_by(if self.layout.best_last() { page } else { -page })
+    }
+
+    pub fn page_down(&mut self) -> String {
+        let page = self.max_choices as isize;
+        self.move_by(if self.layout.best_last() { -page } else { page })
     }
 
     pub fn previous(&mut self) -> String {
-        if self.selected == 0 {
+        if self.selected == 0 && self.cycle {
+            self.keep = None;
             self.selected = self.last_index();
+            self.draw()
         } else {
-            self.selected -= 1;
+            self.move_by(-1)
         }
-
-        self.draw()
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> String {
-        if self.selected == self.last_index() {
+        if self.selected == self.last_index() && self.cycle {
+            self.keep = None;
             self.selected = 0;
+            self.draw()
         } else {
-            self.selected += 1;
+            self.move_by(1)
         }
+    }
+
+    pub fn first(&mut self) -> String {
+        self.keep = None;
+        self.selected = 0;
+        self.draw()
+    }
 
+    pub fn last(&mut self) -> String {
+        self.keep = None;
+        self.selected = self.last_index();
+        self.draw()
+    }
+
+    // Selects the match drawn `offset` lines below the prompt, if there is one.
+    pub fn select_at(&mut self, offset: isize) -> Option<String> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        let range = self.drawn_range();
+        let row = self.layout.row_at(offset, self.fixed_lines().len(), range.len())?;
+        self.keep = None;
+        self.selected = range.start + row;
+
+        Some(self.draw())
+    }
+
+    // Selects the match clicked on, along with whether it was a double click.
+    // Clicks are told apart by the choice they land on rather than the row,
+    // since selecting near an edge scrolls the list under the pointer.
+    pub fn click(&mut self, offset: isize, at: Instant) -> Option<(String, bool)> {
+        let text = self.select_at(offset)?;
+        let clicked = self.matches[self.selected].index;
+        let double = matches!(
+            self.last_click,
+            Some((last, last_at)) if last == clicked && at.duration_since(last_at) < Self::DOUBLE_CLICK
+        );
+        self.last_click = Some((clicked, at));
+
+        Some((text, double))
+    }
+
+    // Moves the selection towards worse matches for a positive delta, stopping
+    // at either end.
+    fn move_by(&mut self, delta: isize) -> String {
+        self.keep = None;
+        self.selected = (self.selected as isize + delta).clamp(0, self.last_index() as isize) as usize;
         self.draw()
     }
 
     pub fn select(&self, terminal: &mut Terminal) {
-        terminal.print(&format!("\r{}", cursor::clear_screen_down()));
-        if !self.matches.is_empty() {
+        terminal.print(&self.cancel());
+        if !self.marked.is_empty() {
+            self.marked().iter().for_each(|returnable| println!("{}", returnable));
+        } else if !self.matches.is_empty() {
             println!("{}", self.current_match().returnable);
         }
     }
 
-    pub fn current_match(&self) -> &Choice {
-        self.matches[self.selected].choice
+    pub fn current_match(&self) -> &Choice<'_> {
+        &self.choices[self.matches[self.selected].index]
+    }
+
+    pub fn current(&self) -> Option<&Choice<'a>> {
+        self.matches.get(self.selected).map(|matched| &self.choices[matched.index])
+    }
+
+    pub fn marked(&self) -> Vec<&str> {
+        self.marked.iter().map(|&index| self.choices[index].returnable.as_ref()).collect()
+    }
+
+    // Marks or unmarks the selected choice and moves on to the next one.
+    pub fn toggle_mark(&mut self) -> String {
+        if let Some(matched) = self.matches.get(self.selected) {
+            match self.marked.iter().position(|&index| index == matched.index) {
+                Some(position) => { self.marked.remove(position); },
+                None => self.marked.push(matched.index),
+            }
+        }
+
+        self.next()
+    }
+
+    // Labels the drawn rows, best match first, until the next key is handled.
+    pub fn start_jump(&mut self) -> String {
+        self.jumping = true;
+        self.draw()
+    }
+
+    // Selects the row with the label, returning whether there was one.
+    pub fn jump(&mut self, label: char) -> bool {
+        let range = self.drawn_range();
+        match Self::JUMP_LABELS.iter().position(|&known| known == label) {
+            Some(row) if self.error.is_none() && row < range.len() => {
+                self.keep = None;
+                self.selected = range.start + row;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn stop_jump(&mut self) -> String {
+        self.jumping = false;
+        self.draw()
     }
 
     pub fn cancel(&self) -> String {
-        format!("\r{}", cursor::clear_screen_down())
+        self.layout.clear(self.reserved())
+    }
+
+    pub fn toggle_exact(&mut self) {
+        self.mode = self.mode.toggle_exact();
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.mode = self.mode.toggle_regex();
     }
 
     pub fn filter(&mut self, query: &[char]) -> String {
@@ -75,24 +287,94 @@ impl<'a> Choices<'a> {
         self.draw()
     }
 
+    // Adds choices that arrived after the initial draw, scoring only the new
+    // ones against the last query and keeping the same choice selected.
+    pub fn extend(&mut self, choices: Vec<Choice<'a>>) -> String {
+        let start = self.choices.len();
+        let selected = self.matches.get(self.selected).map(|matched| matched.index);
+        let choices = self.split_header(choices);
+        self.choices.extend(choices);
+
+        if let Ok(pattern) = Pattern::new(&self.query, self.mode, self.typos) {
+            let matches = self.match_range(&pattern, start..self.choices.len());
+            self.matches.extend(matches);
+            self.matches.par_sort();
+            self.selected = selected
+                .and_then(|index| self.matches.iter().position(|matched| matched.index == index))
+                .unwrap_or(0);
+            self.restore_selection();
+        }
+
+        self.draw()
+    }
+
+    // Swaps in a new set of choices, such as the output of a reloaded source.
+    pub fn replace(&mut self, choices: Vec<Choice<'a>>) -> String {
+        let keep = self.keep.take().or_else(|| self.current_returnable());
+        self.header_choices.clear();
+        self.choices = self.split_header(choices);
+        self.marked.clear();
+        self.filter_choices(&self.query.clone());
+        self.keep = keep;
+        self.restore_selection();
+
+        self.draw()
+    }
+
+    // Moves choices into the header until it has all of its lines.
+    fn split_header(&mut self, mut choices: Vec<Choice<'a>>) -> Vec<Choice<'a>> {
+        let missing = self.header_lines - self.header_choices.len();
+        self.header_choices.extend(choices.drain(..missing.min(choices.len())));
+
+        choices
+    }
+
+    fn current_returnable(&self) -> Option<String> {
+        self.current().map(|choice| choice.returnable.to_string())
+    }
+
+    fn restore_selection(&mut self) {
+        if let Some(keep) = &self.keep {
+            if let Some(position) = self.matches.iter().position(|matched| &self.choices[matched.index].returnable == keep) {
+                self.selected = position;
+                self.keep = None;
+            }
+        }
+    }
+
     fn filter_choices(&mut self, query: &[char]) {
         self.selected = 0;
-        self.matches = self
-            .choices
+        self.keep = None;
+        self.query = query.to_vec();
+
+        match Pattern::new(query, self.mode, self.typos) {
+            Ok(pattern) => {
+                self.error = None;
+                self.matches = self.match_range(&pattern, 0..self.choices.len());
+                self.matches.par_sort();
+            }
+            Err(error) => {
+                self.error = Some(error);
+                self.matches = vec![];
+            }
+        }
+    }
+
+    fn match_range(&self, pattern: &Pattern, range: std::ops::Range<usize>) -> Vec<Match> {
+        self.choices[range.clone()]
             .par_iter()
-            .filter_map(|choice| Match::new(query, choice))
-            .collect::<Vec<Match<'a>>>();
-        self.matches.par_sort();
+            .zip(range)
+            .filter_map(|(choice, index)| Match::new(pattern, index, choice, &self.profile))
+            .collect()
     }
 
-    fn draw(&self) -> String {
-        format!(
-            "{}\r\n{}\r{}{}",
-            cursor::save_position(),
-            cursor::clear_screen_down(),
-            self.draw_choices(),
-            cursor::restore_position(),
-        )
+    pub fn draw(&self) -> String {
+        self.layout.draw(&self.lines(), self.reserved())
+    }
+
+    // Lines drawn besides the prompt, taking up at most this many.
+    fn reserved(&self) -> usize {
+        self.fixed_lines().len() + self.max_choices
     }
 
     fn last_index(&self) -> usize {
@@ -103,11 +385,61 @@ impl<'a> Choices<'a> {
         }
     }
 
-    fn draw_choices(&self) -> String {
-        self.drawn_range()
-            .map(|i| self.matches[i].draw(i == self.selected, self.show_scores))
-            .collect::<Vec<String>>()
-            .join("\n\r")
+    fn lines(&self) -> Vec<String> {
+        let rows = match &self.error {
+            Some(error) => vec![cursor::nowrap(error)],
+            None => self.drawn_range().enumerate().map(|(row, i)| {
+                let matched = &self.matches[i];
+                let options = DrawOptions {
+                    selected: i == self.selected,
+                    indent: if self.jumping { Self::LABEL_WIDTH } else { 0 },
+                    ..self.draw_options
+                };
+                let mut line = matched.draw(&self.choices[matched.index], &options, &self.theme);
+
+                if self.marked.contains(&matched.index) {
+                    line = color::bold(&line);
+                }
+                match Self::JUMP_LABELS.get(row) {
+                    Some(label) if self.jumping => format!("{} {}", self.theme.label(&label.to_string()), line),
+                    _ if self.jumping => format!("  {}", line),
+                    _ => line,
+                }
+            }).collect(),
+        };
+
+        self.layout.arrange(self.fixed_lines(), rows, self.max_choices)
+    }
+
+    // The header and status lines drawn above the choices. Header lines that
+    // haven't streamed in yet are left blank, so the lines reserved on the
+    // first draw stay enough.
+    fn fixed_lines(&self) -> Vec<String> {
+        let missing = self.header_lines - self.header_choices.len();
+
+        self.header
+            .iter()
+            .map(|line| line.as_str())
+            .chain(self.header_choices.iter().map(|choice| choice.display.as_ref()))
+            .map(|line| cursor::nowrap(&self.theme.header(line)))
+            .chain(std::iter::repeat_n(String::new(), missing))
+            .chain(std::iter::once(cursor::nowrap(&self.status())))
+            .collect()
+    }
+
+    fn status(&self) -> String {
+        let spinner = if self.loading { Self::SPINNER[self.spinner % Self::SPINNER.len()] } else { ' ' };
+        let mut status = format!("{} {}/{}", spinner, self.matches.len(), self.choices.len());
+
+        if !self.marked.is_empty() {
+            status.push_str(&format!(" ({} marked)", self.marked.len()));
+        }
+        if self.skipped > 0 {
+            let noun = if self.skipped == 1 { "line" } else { "lines" };
+            status.push_str(&format!(", {} invalid {} skipped", self.skipped, noun));
+        }
+
+        status
     }
 
     fn max_choices(&self) -> usize {
@@ -138,8 +470,6 @@ impl<'a> Choices<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::color;
-    use crate::config::Config;
 
     #[test]
     fn test_new() {
@@ -149,13 +479,13 @@ mod tests {
             make_choice("baz"),
             make_choice("boo"),
         ];
-        let choices = Choices::new(4, &input, false);
+        let choices = Choices::new(&config(4), input);
 
         assert_eq!(4, choices.max_choices);
         assert_eq!(0, choices.selected);
         assert_eq!(
             vec!["foo", "bar", "baz", "boo"],
-            choices.choices.iter().map(|choice| choice.searchable).collect::<Vec<&str>>(),
+            choices.choices.iter().map(|choice| choice.searchable.as_ref()).collect::<Vec<&str>>(),
         );
     }
 
@@ -167,7 +497,7 @@ mod tests {
             make_choice("baz"),
             make_choice("boo"),
         ];
-        let choices = Choices::new(2, &input, false);
+        let choices = Choices::new(&config(2), input);
 
         assert_eq!(2, choices.max_choices);
     }
@@ -177,13 +507,14 @@ mod tests {
         let input: Vec<Choice> = vec![
             make_choice("foo"), make_choice("bar"),
         ];
-        let mut choices = Choices::new(4, &input, false);
+        let mut choices = Choices::new(&config(4), input);
 
         assert_eq!(
             format!(
-                "{}\r\n{}\r{}\n\r{}{}",
+                "{}\r\n{}\r{}\n\r{}\n\r{}{}",
                 cursor::save_position(),
                 cursor::clear_screen_down(),
+                cursor::nowrap("  2/2"),
                 cursor::nowrap(&color::inverse("foo")),
                 cursor::nowrap("bar"),
                 cursor::restore_position()
@@ -195,25 +526,169 @@ mod tests {
             choices
                 .matches
                 .iter()
-                .map(|matcher| matcher.choice.searchable)
+                .map(|matcher| choices.choices[matcher.index].searchable.as_ref())
                 .collect::<Vec<&str>>()
         );
     }
 
+    #[test]
+    fn test_toggle_exact() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"), make_choice("f_o_o"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+        choices.filter(&['f', 'o']);
+        assert_eq!(2, choices.matches.len());
+
+        choices.toggle_exact();
+        choices.filter(&['f', 'o']);
+        assert_eq!(1, choices.matches.len());
+        assert_eq!("foo", choices.current_match().searchable);
+    }
+
+    #[test]
+    fn test_toggle_regex() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"), make_choice("f_o_o"), make_choice("bar"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+
+        choices.toggle_regex();
+        choices.filter(&['f', '.', 'o', '.']);
+        assert_eq!(1, choices.matches.len());
+        assert_eq!("f_o_o", choices.current_match().searchable);
+    }
+
+    #[test]
+    fn test_filter_invalid_regex() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"), make_choice("bar"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+        choices.toggle_regex();
+
+        assert_eq!(
+            format!(
+                "{}\r\n{}\r{}\n\r{}{}",
+                cursor::save_position(),
+                cursor::clear_screen_down(),
+                cursor::nowrap("  0/2"),
+                cursor::nowrap("error: unclosed group"),
+                cursor::restore_position()
+            ),
+            choices.filter(&['('])
+        );
+        assert!(choices.matches.is_empty());
+
+        choices.filter(&['(', ')']);
+        assert!(choices.error.is_none());
+        assert_eq!(2, choices.matches.len());
+    }
+
+    #[test]
+    fn test_filter_with_header() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"), make_choice("bar"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+        choices.add_header(vec!["name".to_string()]);
+
+        assert_eq!(
+            format!(
+                "{}\r\n{}\r{}\n\r{}\n\r{}\n\r{}{}",
+                cursor::save_position(),
+                cursor::clear_screen_down(),
+                cursor::nowrap("name"),
+                cursor::nowrap("  2/2"),
+                cursor::nowrap(&color::inverse("foo")),
+                cursor::nowrap("bar"),
+                cursor::restore_position()
+            ),
+            choices.filter(&[])
+        );
+    }
+
+    #[test]
+    fn test_header_lines() {
+        let input: Vec<Choice> = vec![make_choice("NAME")];
+        let mut choices = Choices::new(&Config { header_lines: 2, ..config(4) }, input);
+        choices.extend(vec![make_choice("SIZE"), make_choice("foo")]);
+
+        assert_eq!(
+            format!(
+                "{}\r\n{}\r{}\n\r{}\n\r{}\n\r{}{}",
+                cursor::save_position(),
+                cursor::clear_screen_down(),
+                cursor::nowrap("NAME"),
+                cursor::nowrap("SIZE"),
+                cursor::nowrap("  1/1"),
+                cursor::nowrap(&color::inverse("foo")),
+                cursor::restore_position()
+            ),
+            choices.filter(&[])
+        );
+    }
+
+    #[test]
+    fn test_header_lines_reserved_up_front() {
+        let mut choices = Choices::new(&Config { header_lines: 2, ..config(4) }, vec![]);
+        let reserved = choices.reserved();
+        assert_eq!(7, reserved);
+
+        choices.extend(vec![make_choice("NAME"), make_choice("SIZE"), make_choice("foo")]);
+        assert_eq!(reserved, choices.reserved());
+        choices.replace(vec![make_choice("NAME")]);
+        assert_eq!(reserved, choices.reserved());
+        assert_eq!(String::new(), choices.fixed_lines()[1]);
+    }
+
+    #[test]
+    fn test_status_while_loading() {
+        let mut choices = Choices::new(&config(4), vec![make_choice("foo")]);
+        choices.set_loading(true);
+        choices.filter(&['x']);
+
+        assert_eq!("⠋ 0/1", choices.status());
+        choices.tick();
+        assert_eq!("⠙ 0/1", choices.status());
+        choices.set_loading(false);
+        assert_eq!("  0/1", choices.status());
+    }
+
+    #[test]
+    fn test_filter_with_skipped() {
+        let input: Vec<Choice> = vec![make_choice("foo")];
+        let mut choices = Choices::new(&config(4), input);
+        choices.set_skipped(2);
+
+        assert_eq!(
+            format!(
+                "{}\r\n{}\r{}\n\r{}{}",
+                cursor::save_position(),
+                cursor::clear_screen_down(),
+                cursor::nowrap("  1/1, 2 invalid lines skipped"),
+                cursor::nowrap(&color::inverse("foo")),
+                cursor::restore_position()
+            ),
+            choices.filter(&[])
+        );
+    }
+
     #[test]
     fn test_previous_when_wrapping() {
         let input: Vec<Choice> = vec![
             make_choice("foo"),
             make_choice("bar"),
         ];
-        let mut choices = Choices::new(4, &input, false);
+        let mut choices = Choices::new(&config(4), input);
         choices.filter(&[]);
 
         assert_eq!(
             format!(
-                "{}\r\n{}\r{}\n\r{}{}",
+                "{}\r\n{}\r{}\n\r{}\n\r{}{}",
                 cursor::save_position(),
                 cursor::clear_screen_down(),
+                cursor::nowrap("  2/2"),
                 cursor::nowrap("foo"),
                 cursor::nowrap(&color::inverse("bar")),
                 cursor::restore_position(),
@@ -229,15 +704,16 @@ mod tests {
             make_choice("foo"),
             make_choice("bar"),
         ];
-        let mut choices = Choices::new(4, &input, false);
+        let mut choices = Choices::new(&config(4), input);
         choices.filter(&[]);
         choices.selected = 1;
 
         assert_eq!(
             format!(
-                "{}\r\n{}\r{}\n\r{}{}",
+                "{}\r\n{}\r{}\n\r{}\n\r{}{}",
                 cursor::save_position(),
                 cursor::clear_screen_down(),
+                cursor::nowrap("  2/2"),
                 cursor::nowrap(&color::inverse("foo")),
                 cursor::nowrap("bar"),
                 cursor::restore_position(),
@@ -253,15 +729,16 @@ mod tests {
             make_choice("foo"),
             make_choice("bar"),
         ];
-        let mut choices = Choices::new(4, &input, false);
+        let mut choices = Choices::new(&config(4), input);
         choices.filter(&[]);
         choices.selected = 1;
 
         assert_eq!(
             format!(
-                "{}\r\n{}\r{}\n\r{}{}",
+                "{}\r\n{}\r{}\n\r{}\n\r{}{}",
                 cursor::save_position(),
                 cursor::clear_screen_down(),
+                cursor::nowrap("  2/2"),
                 cursor::nowrap(&color::inverse("foo")),
                 cursor::nowrap("bar"),
                 cursor::restore_position(),
@@ -277,14 +754,15 @@ mod tests {
             make_choice("foo"),
             make_choice("bar"),
         ];
-        let mut choices = Choices::new(4, &input, false);
+        let mut choices = Choices::new(&config(4), input);
         choices.filter(&[]);
 
         assert_eq!(
             format!(
-                "{}\r\n{}\r{}\n\r{}{}",
+                "{}\r\n{}\r{}\n\r{}\n\r{}{}",
                 cursor::save_position(),
                 cursor::clear_screen_down(),
+                cursor::nowrap("  2/2"),
                 cursor::nowrap("foo"),
                 cursor::nowrap(&color::inverse("bar")),
                 cursor::restore_position(),
@@ -294,13 +772,125 @@ mod tests {
         assert_eq!(1, choices.selected);
     }
 
+    #[test]
+    fn test_up_in_reverse() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"),
+            make_choice("bar"),
+        ];
+        let mut choices = Choices::new(&Config { layout: Layout::Reverse, ..config(4) }, input);
+        choices.filter(&[]);
+
+        choices.up();
+        assert_eq!(1, choices.selected);
+        choices.down();
+        assert_eq!(0, choices.selected);
+    }
+
+    #[test]
+    fn test_paging() {
+        let mut choices = Choices::new(&config(4), ten_choices());
+        choices.filter(&[]);
+
+        choices.page_down();
+        assert_eq!(4, choices.selected);
+        assert_eq!(2..6, choices.drawn_range());
+        choices.page_down();
+        choices.page_down();
+        assert_eq!(9, choices.selected);
+        assert_eq!(6..10, choices.drawn_range());
+        choices.page_up();
+        assert_eq!(5, choices.selected);
+        assert_eq!(3..7, choices.drawn_range());
+        choices.first();
+        assert_eq!(0..4, choices.drawn_range());
+        choices.last();
+        assert_eq!(9, choices.selected);
+    }
+
+    #[test]
+    fn test_no_cycle() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"),
+            make_choice("bar"),
+        ];
+        let mut choices = Choices::new(&Config { cycle: false, ..config(4) }, input);
+        choices.filter(&[]);
+
+        choices.previous();
+        assert_eq!(0, choices.selected);
+        choices.next();
+        choices.next();
+        assert_eq!(1, choices.selected);
+    }
+
+    #[test]
+    fn test_select_at() {
+        let mut choices = Choices::new(&config(4), ten_choices());
+        choices.filter(&[]);
+        choices.last();
+
+        assert!(choices.select_at(1).is_none());
+        assert!(choices.select_at(3).is_some());
+        assert_eq!(7, choices.selected);
+        assert!(choices.select_at(6).is_none());
+        assert_eq!(7, choices.selected);
+    }
+
+    #[test]
+    fn test_double_click() {
+        let mut choices = Choices::new(&config(4), ten_choices());
+        choices.filter(&[]);
+        choices.last();
+        let now = Instant::now();
+
+        // The first click scrolls the list, so the second lands on another choice.
+        assert!(!choices.click(3, now).unwrap().1);
+        assert_eq!(7, choices.selected);
+        assert!(!choices.click(3, now).unwrap().1);
+        assert_eq!(6, choices.selected);
+        assert!(choices.click(4, now).unwrap().1);
+        assert!(!choices.click(4, now + Duration::from_secs(1)).unwrap().1);
+    }
+
+    #[test]
+    fn test_jump() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"),
+            make_choice("bar"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+        choices.filter(&[]);
+
+        assert_eq!(
+            format!(
+                "{}\r\n{}\r{}{}",
+                cursor::save_position(),
+                cursor::clear_screen_down(),
+                [
+                    cursor::nowrap("  2/2"),
+                    format!("{} {}", Theme::dark().label("a"), cursor::nowrap(&color::inverse("foo"))),
+                    format!("{} {}", Theme::dark().label("s"), cursor::nowrap("bar")),
+                ].join("\n\r"),
+                cursor::restore_position(),
+            ),
+            choices.start_jump()
+        );
+        assert!(!choices.jump('d'));
+        assert!(choices.jump('s'));
+        assert_eq!(1, choices.selected);
+
+        choices.stop_jump();
+        assert!(!choices.jumping);
+    }
+
     #[test]
     fn test_cancel() {
         let input: Vec<Choice> = vec![
             make_choice("foo"),
             make_choice("bar"),
         ];
-        let choices = Choices::new(4, &input, false);
+        let choices = Choices::new(&config(4), input);
 
         assert_eq!(
             format!("\r{}", cursor::clear_screen_down()),
@@ -308,13 +898,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extend() {
+        let input: Vec<Choice> = vec![
+            make_choice("xbx"),
+            make_choice("foo"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+        choices.filter(&['b']);
+        assert_eq!(1, choices.matches.len());
+
+        choices.extend(vec![make_choice("bar"), make_choice("qux")]);
+
+        assert_eq!(
+            vec!["bar", "xbx"],
+            choices
+                .matches
+                .iter()
+                .map(|matcher| choices.choices[matcher.index].searchable.as_ref())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!("xbx", choices.current_match().searchable);
+    }
+
+    #[test]
+    fn test_replace_keeps_selection() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"),
+            make_choice("bar"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+        choices.filter(&[]);
+        choices.next();
+
+        choices.replace(vec![make_choice("baz")]);
+        assert_eq!("baz", choices.current_match().searchable);
+
+        choices.extend(vec![make_choice("bar")]);
+        assert_eq!("bar", choices.current_match().searchable);
+
+        choices.replace(vec![make_choice("qux"), make_choice("bar")]);
+        assert_eq!("bar", choices.current_match().searchable);
+    }
+
+    #[test]
+    fn test_toggle_mark() {
+        let input: Vec<Choice> = vec![
+            make_choice("foo"),
+            make_choice("bar"),
+            make_choice("baz"),
+        ];
+        let mut choices = Choices::new(&config(4), input);
+        choices.filter(&[]);
+
+        assert_eq!(
+            format!(
+                "{}\r\n{}\r{}\n\r{}\n\r{}\n\r{}{}",
+                cursor::save_position(),
+                cursor::clear_screen_down(),
+                cursor::nowrap("  3/3 (1 marked)"),
+                color::bold(&cursor::nowrap("foo")),
+                cursor::nowrap(&color::inverse("bar")),
+                cursor::nowrap("baz"),
+                cursor::restore_position(),
+            ),
+            choices.toggle_mark()
+        );
+        choices.next();
+        choices.toggle_mark();
+        assert_eq!(vec!["foo", "baz"], choices.marked());
+
+        choices.toggle_mark();
+        assert_eq!(vec!["baz"], choices.marked());
+    }
+
     #[test]
     fn test_current_match() {
         let input: Vec<Choice> = vec![
             make_choice("foo"),
             make_choice("bar"),
         ];
-        let mut choices = Choices::new(4, &input, false);
+        let mut choices = Choices::new(&config(4), input);
         choices.filter(&[]);
 
         assert_eq!("foo", choices.current_match().searchable);
@@ -322,7 +986,7 @@ mod tests {
 
     #[bench]
     fn bench_filtering(b: &mut test::Bencher) {
-        let choices: Vec<Choice> = vec![
+        let input: Vec<Choice> = vec![
             make_choice("CODE_OF_CONDUCT.md"),
             make_choice("Cargo.lock"),
             make_choice("Cargo.toml"),
@@ -336,16 +1000,20 @@ mod tests {
         ];
         let query = ['c', 'o', 'd', 'e'];
 
-        b.iter(|| Choices::new(10, &choices, false).filter(&query))
+        let mut choices = Choices::new(&config(10), input);
+
+        b.iter(|| choices.filter(&query))
     }
 
-    fn make_choice(choice: &str) -> Choice {
-        let config = Config {
-            lines: 10, prompt: ">".to_string(), show_scores: false,
-            query: None, delimiter: None, field: None, output: None,
-            benchmark: false,
-        };
+    fn make_choice(choice: &str) -> Choice<'_> {
+        Choice::new(choice, &Config::default())
+    }
+
+    fn ten_choices() -> Vec<Choice<'static>> {
+        ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"].into_iter().map(make_choice).collect()
+    }
 
-        Choice::new(choice, &config)
+    fn config(lines: usize) -> Config {
+        Config { lines, ..Config::default() }
     }
 }
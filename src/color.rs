@@ -2,8 +2,202 @@ pub fn inverse(text: &str) -> String {
     format!("\x1B[7m{}\x1B[27m", text)
 }
 
-pub fn highlight(c: char) -> String {
-    format!("\x1B[33m{}\x1B[39m", c)
+pub fn bold(text: &str) -> String {
+    format!("\x1B[1m{}\x1B[22m", text)
+}
+
+pub fn underline(text: &str) -> String {
+    format!("\x1B[4m{}\x1B[24m", text)
+}
+
+const NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    // One of the 16 colors the terminal's palette defines, bright ones from 8.
+    Named(u8),
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    // Takes a name like `red` or `bright-red`, an index into the 256 color
+    // palette or a `#rrggbb` value.
+    pub fn parse(spec: &str) -> Option<Color> {
+        if let Some(hex) = spec.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        if let Ok(index) = spec.parse() {
+            return Some(Color::Indexed(index));
+        }
+
+        let (bright, name) = match spec.strip_prefix("bright-") {
+            Some(name) => (8, name),
+            None => (0, spec),
+        };
+        NAMES.iter().position(|&known| known == name).map(|i| Color::Named(i as u8 + bright))
+    }
+
+    // The SGR parameters for this color, as a foreground or a background.
+    // Without truecolor support RGB values fall back to the closest color in
+    // the 256 color cube.
+    fn sgr(self, background: bool, truecolor: bool) -> String {
+        let base = if background { 40 } else { 30 };
+
+        match self {
+            Color::Named(n) if n < 8 => (base + n).to_string(),
+            Color::Named(n) => (base + 60 + n - 8).to_string(),
+            Color::Indexed(n) => format!("{};5;{}", base + 8, n),
+            Color::Rgb(r, g, b) if truecolor => format!("{};2;{};{};{}", base + 8, r, g, b),
+            Color::Rgb(r, g, b) => {
+                let cube = |c: u8| (u16::from(c) * 5 + 127) / 255;
+                format!("{};5;{}", base + 8, 16 + 36 * cube(r) + 6 * cube(g) + cube(b))
+            }
+        }
+    }
+}
+
+// The colors used for each part of the interface, `None` leaving that part in
+// the terminal's own colors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub matched: Option<Color>,
+    pub matched_selected: Option<Color>,
+    pub prompt: Option<Color>,
+    // Background of the selected row, which is drawn in inverse video without one.
+    pub pointer: Option<Color>,
+    pub header: Option<Color>,
+    pub score: Option<Color>,
+    // Labels drawn on the rows while jumping, always in bold so they stand
+    // out from highlights even without colors.
+    pub label: Option<Color>,
+    // Off for NO_COLOR and terminals without color, matched characters are
+    // underlined instead.
+    pub enabled: bool,
+    pub truecolor: bool,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 2] = ["dark", "light"];
+    pub const ELEMENTS: [&'static str; 7] = ["hl", "hl+", "prompt", "pointer", "header", "score", "label"];
+
+    pub fn dark() -> Theme {
+        Theme {
+            matched: Some(Color::Named(3)),
+            matched_selected: Some(Color::Named(3)),
+            prompt: None,
+            pointer: None,
+            header: None,
+            score: None,
+            label: Some(Color::Named(1)),
+            enabled: true,
+            truecolor: false,
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            matched: Some(Color::Named(4)),
+            matched_selected: Some(Color::Named(4)),
+            prompt: Some(Color::Named(4)),
+            pointer: None,
+            header: Some(Color::Named(8)),
+            score: Some(Color::Named(8)),
+            ..Theme::dark()
+        }
+    }
+
+    // Reads a comma separated list of `element:color` pairs, which may start
+    // with the name of a theme to build on instead of the dark one.
+    pub fn parse(spec: &str) -> Result<Theme, String> {
+        let mut theme = Theme::dark();
+
+        for (i, part) in spec.split(',').map(str::trim).filter(|part| !part.is_empty()).enumerate() {
+            let (element, value) = match part.split_once(':') {
+                Some(pair) => pair,
+                None if i == 0 && part == "dark" => continue,
+                None if i == 0 && part == "light" => {
+                    theme = Theme::light();
+                    continue;
+                }
+                None => return Err(format!("Unknown theme `{}`, expected one of {}", part, Self::NAMES.join(", "))),
+            };
+            let color = Color::parse(value)
+                .ok_or_else(|| format!("Invalid color `{}` for {}", value, element))?;
+
+            let slot = match element {
+                "hl" => &mut theme.matched,
+                "hl+" => &mut theme.matched_selected,
+                "prompt" => &mut theme.prompt,
+                "pointer" => &mut theme.pointer,
+                "header" => &mut theme.header,
+                "score" => &mut theme.score,
+                "label" => &mut theme.label,
+                _ => return Err(format!("Unknown color element `{}`, expected one of {}", element, Self::ELEMENTS.join(", "))),
+            };
+            *slot = Some(color);
+        }
+
+        Ok(theme)
+    }
+
+    pub fn matched(&self, text: &str, selected: bool) -> String {
+        if !self.enabled {
+            return underline(text);
+        }
+
+        self.paint(if selected { self.matched_selected } else { self.matched }, text)
+    }
+
+    pub fn prompt(&self, text: &str) -> String {
+        self.paint(self.prompt, text)
+    }
+
+    pub fn header(&self, text: &str) -> String {
+        self.paint(self.header, text)
+    }
+
+    pub fn score(&self, text: &str) -> String {
+        self.paint(self.score, text)
+    }
+
+    pub fn label(&self, text: &str) -> String {
+        bold(&self.paint(self.label, text))
+    }
+
+    pub fn selected(&self, text: &str) -> String {
+        match self.pointer {
+            Some(_) if self.enabled => format!("{}{}\x1B[49m", self.selection(), text),
+            _ => inverse(text),
+        }
+    }
+
+    // The sequence the selected row starts with, for turning it back on after
+    // something resets it.
+    pub fn selection(&self) -> String {
+        match self.pointer {
+            Some(color) if self.enabled => format!("\x1B[{}m", color.sgr(true, self.truecolor)),
+            _ => "\x1B[7m".to_string(),
+        }
+    }
+
+    fn paint(&self, color: Option<Color>, text: &str) -> String {
+        match color {
+            Some(color) if self.enabled => format!("\x1B[{}m{}\x1B[39m", color.sgr(false, self.truecolor), text),
+            _ => text.to_string(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::dark()
+    }
 }
 
 #[cfg(test)]
@@ -16,7 +210,66 @@ mod tests {
     }
 
     #[test]
-    fn test_highlight() {
-        assert_eq!("\x1B[33mf\x1B[39m", highlight('f'));
+    fn test_bold() {
+        assert_eq!("\x1B[1mfoo\x1B[22m", bold("foo"));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(Some(Color::Named(1)), Color::parse("red"));
+        assert_eq!(Some(Color::Named(9)), Color::parse("bright-red"));
+        assert_eq!(Some(Color::Indexed(208)), Color::parse("208"));
+        assert_eq!(Some(Color::Rgb(255, 136, 0)), Color::parse("#ff8800"));
+        assert_eq!(None, Color::parse("#ff88"));
+        assert_eq!(None, Color::parse("256"));
+        assert_eq!(None, Color::parse("purple"));
+    }
+
+    #[test]
+    fn test_sgr() {
+        assert_eq!("33", Color::Named(3).sgr(false, false));
+        assert_eq!("101", Color::Named(9).sgr(true, false));
+        assert_eq!("38;5;208", Color::Indexed(208).sgr(false, false));
+        assert_eq!("38;2;255;136;0", Color::Rgb(255, 136, 0).sgr(false, true));
+        assert_eq!("38;5;214", Color::Rgb(255, 136, 0).sgr(false, false));
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        let theme = Theme::parse("light,hl:red,pointer:236").unwrap();
+
+        assert_eq!(Some(Color::Named(1)), theme.matched);
+        assert_eq!(Some(Color::Named(4)), theme.matched_selected);
+        assert_eq!(Some(Color::Indexed(236)), theme.pointer);
+        assert!(Theme::parse("hl:purple").is_err());
+        assert!(Theme::parse("border:red").is_err());
+        assert!(Theme::parse("solarized").is_err());
+    }
+
+    #[test]
+    fn test_matched() {
+        let mut theme = Theme::dark();
+        assert_eq!("\x1B[33mf\x1B[39m", theme.matched("f", false));
+
+        theme.enabled = false;
+        assert_eq!("\x1B[4mf\x1B[24m", theme.matched("f", false));
+    }
+
+    #[test]
+    fn test_label() {
+        let mut theme = Theme::dark();
+        assert_eq!("\x1B[1m\x1B[31ma\x1B[39m\x1B[22m", theme.label("a"));
+
+        theme.enabled = false;
+        assert_eq!("\x1B[1ma\x1B[22m", theme.label("a"));
+    }
+
+    #[test]
+    fn test_selected() {
+        let mut theme = Theme::dark();
+        assert_eq!("\x1B[7mfoo\x1B[27m", theme.selected("foo"));
+
+        theme.pointer = Some(Color::Indexed(236));
+        assert_eq!("\x1B[48;5;236mfoo\x1B[49m", theme.selected("foo"));
     }
 }
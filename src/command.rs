@@ -0,0 +1,215 @@
+use crate::events::{self, Event};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long a stopped command gets to exit before it's killed.
+const STOP_GRACE: Duration = Duration::from_millis(200);
+
+// The values placeholders in a command are replaced with.
+pub struct Placeholders<'p> {
+    pub current: Option<&'p str>,
+    pub query: &'p str,
+    pub marked: Vec<&'p str>,
+}
+
+// Fills in the placeholders of a command: `{}` is the current choice, `{q}` the
+// query and `{+}` every marked choice, or the current one when none are marked.
+// Values are quoted so each reaches the command as a single argument.
+pub fn expand(template: &str, placeholders: &Placeholders) -> String {
+    let mut expanded = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let (value, length) = if rest.starts_with("{}") {
+            (quote(placeholders.current.unwrap_or_default()), 2)
+        } else if rest.starts_with("{q}") {
+            (quote(placeholders.query), 3)
+        } else if rest.starts_with("{+}") {
+            let marked = match placeholders.marked.as_slice() {
+                [] => placeholders.current.into_iter().collect(),
+                marked => marked.to_vec(),
+            };
+            (marked.iter().map(|text| quote(text)).collect::<Vec<String>>().join(" "), 3)
+        } else {
+            ("{".to_string(), 1)
+        };
+
+        expanded.push_str(&value);
+        rest = &rest[length..];
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
+
+pub fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+// Runs a command through the shell and streams its output lines as choices.
+// The shell leads its own process group so stopping it stops every command in
+// a pipeline too.
+pub fn stream(command: &str, generation: usize, events: Sender<Event>) -> io::Result<Child> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+
+    let stdout = child.stdout.take().unwrap();
+    let (lines, read) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    events::batch(read, generation, events);
+
+    Ok(child)
+}
+
+// Runs a command in the foreground with the terminal as its stdin and output.
+pub fn run(command: &str, tty: &File) -> io::Result<()> {
+    Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(tty.try_clone()?)
+        .stdout(tty.try_clone()?)
+        .stderr(tty.try_clone()?)
+        .status()
+        .map(|_| ())
+}
+
+// Runs a command in the background without any access to the terminal.
+pub fn run_silent(command: &str) -> io::Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+// Asks the command's process group to stop, killing it when it hasn't after
+// STOP_GRACE so a command that ignores SIGTERM can't hang the search.
+pub fn stop(mut child: Child) {
+    let group = -(child.id() as libc::pid_t);
+    // SAFETY: kill has no memory safety requirements. The group is the one
+    // stream started and it isn't reaped until the wait below, so its id
+    // can't have been reused by another group yet.
+    unsafe {
+        libc::kill(group, libc::SIGTERM);
+    }
+
+    let deadline = Instant::now() + STOP_GRACE;
+    while Instant::now() < deadline {
+        if !matches!(child.try_wait(), Ok(None)) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // SAFETY: as above, the shell still hasn't been reaped.
+    unsafe {
+        libc::kill(group, libc::SIGKILL);
+    }
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let placeholders = Placeholders { current: Some("a b"), query: "it's", marked: vec![] };
+
+        assert_eq!("rg --line-number 'it'\\''s'", expand("rg --line-number {q}", &placeholders));
+        assert_eq!("vim 'a b'", expand("vim {}", &placeholders));
+        assert_eq!("ls 'a b'", expand("ls {+}", &placeholders));
+        assert_eq!("awk '{print}' 'a b'", expand("awk '{print}' {}", &placeholders));
+        assert_eq!("ls", expand("ls", &placeholders));
+    }
+
+    #[test]
+    fn test_expand_marked() {
+        let placeholders = Placeholders { current: Some("c"), query: "", marked: vec!["a", "b"] };
+
+        assert_eq!("rm 'a' 'b'", expand("rm {+}", &placeholders));
+        assert_eq!("echo {q", expand("echo {q", &placeholders));
+    }
+
+    #[test]
+    fn test_stream() {
+        let (sender, receiver) = mpsc::channel();
+        let mut child = stream("printf 'a\\nb\\n'", 3, sender).unwrap();
+        child.wait().unwrap();
+
+        let mut lines = vec![];
+        for event in receiver.iter() {
+            match event {
+                Event::Choices(3, batch) => lines.extend(batch),
+                Event::Tick(3) => (),
+                Event::Done(3) => break,
+                _ => panic!("unexpected event"),
+            }
+        }
+
+        assert_eq!(vec!["a", "b"], lines);
+    }
+
+    // Waits for the first event matching `expected`, failing if it takes longer
+    // than a command that's still running would.
+    fn wait_for(receiver: &mpsc::Receiver<Event>, expected: impl Fn(&Event) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(event) if expected(&event) => break,
+                Ok(_) => (),
+                Err(error) => panic!("no matching event: {}", error),
+            }
+        }
+    }
+
+    // The pipeline prints once both sides are running, and its output only
+    // ends once `cat` has exited along with the shell, well before `sleep` would.
+    fn assert_stops(command: &str) {
+        let (sender, receiver) = mpsc::channel();
+        let child = stream(command, 0, sender).unwrap();
+        wait_for(&receiver, |event| matches!(event, Event::Choices(..)));
+
+        let stopping = Instant::now();
+        stop(child);
+
+        assert!(stopping.elapsed() < Duration::from_secs(5));
+        wait_for(&receiver, |event| matches!(event, Event::Done(_)));
+    }
+
+    #[test]
+    fn test_stop_pipeline() {
+        assert_stops("sleep 30 | (echo started; cat)");
+    }
+
+    #[test]
+    fn test_stop_ignoring_term() {
+        assert_stops("trap '' TERM; sleep 30 | (echo started; cat)");
+    }
+}
@@ -1,6 +1,26 @@
 use regex::Regex;
 use terminal_size::{Width, Height, terminal_size};
 use clap::{App, AppSettings, Arg, ArgMatches, SubCommand, Error};
+use crate::color::Theme;
+use crate::fields::FieldRange;
+use crate::layout::Layout;
+use crate::profile::ScoringProfile;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Csv,
+    Tsv,
+    Json,
+}
+
+// What the built-in walker lists when there's nothing on stdin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WalkerOptions {
+    pub files: bool,
+    pub dirs: bool,
+    pub hidden: bool,
+    pub follow: bool,
+}
 
 pub struct Config {
     pub lines: usize,
@@ -8,28 +28,79 @@ pub struct Config {
     pub show_scores: bool,
     pub query: Option<String>,
     pub delimiter: Option<Regex>,
-    pub field: Option<usize>,
-    pub output: Option<usize>,
+    pub format: Option<Format>,
+    pub search_fields: Option<Vec<FieldRange>>,
+    pub display_fields: Option<Vec<FieldRange>>,
+    pub output_fields: Option<Vec<FieldRange>>,
     pub benchmark: bool,
+    pub max_length: usize,
+    pub profile: ScoringProfile,
+    pub exact: bool,
+    pub regex: bool,
+    pub typos: usize,
+    pub bindings: Vec<String>,
+    pub walker: WalkerOptions,
+    pub source: Option<String>,
+    pub header: Vec<String>,
+    pub header_lines: usize,
+    pub layout: Layout,
+    pub cycle: bool,
+    pub mouse: bool,
+    pub vi: bool,
+    pub color: Theme,
+    pub ansi: bool,
+    pub ansi_output: bool,
+    pub keep_right: bool,
 }
 
 impl Config {
     pub fn new() -> Self {
         let matches = Self::menu().get_matches();
+        let settings = read_config_file();
 
-        let lines       = parse_lines(&matches);
         let prompt      = value_t_or_exit!(matches, "prompt", String);
         let show_scores = matches.is_present("show-scores");
         let query       = parse_query(&matches);
+        let max_length  = value_t_or_exit!(matches, "max-length", usize);
+        let profile     = parse_profile(&matches, &settings);
+        let exact       = matches.is_present("exact");
+        let regex       = matches.is_present("regex");
+        let typos       = value_t_or_exit!(matches, "typos", usize);
+        let bindings    = parse_bindings(&matches);
+        let walker      = parse_walker(&matches);
+        let source      = matches.value_of("source").map(String::from);
+        let header      = matches.value_of("header").map_or(vec![], |text| text.lines().map(String::from).collect());
+        let header_lines = value_t_or_exit!(matches, "header-lines", usize);
+        let layout      = Layout::from_name(matches.value_of("layout").unwrap()).unwrap();
+        let cycle       = !matches.is_present("no-cycle");
+        let mouse       = matches.is_present("mouse");
+        let vi          = matches.is_present("vi");
+        let color       = parse_color(&matches);
+        let ansi_output = matches.is_present("ansi-output");
+        let ansi        = ansi_output || matches.is_present("ansi");
+        let keep_right  = matches.is_present("keep-right");
 
         let delimiter   = parse_delimiter(&matches);
-        let field       = subcommand_usize_value_or_exit(&matches, "field");
-        let output      = subcommand_usize_value_or_exit(&matches, "output");
-
-        let benchmark   = match matches.subcommand_name() {
-            Some("benchmark") => true, _ => false
+        let format      = parse_format(&matches);
+        // The header, a CSV or TSV header row and the status line all take
+        // up lines of their own.
+        let header_row  = usize::from(matches!(format, Some(Format::Csv | Format::Tsv)));
+        let lines       = parse_lines(&matches, header.len() + header_lines + header_row + 1);
+        let (search_fields, display_fields, output_fields) = if matches.is_present("json") {
+            (
+                Some(json_paths_or_exit(&matches, "search").unwrap_or_else(|| vec![FieldRange::Name(".".to_string())])),
+                json_paths_or_exit(&matches, "display"),
+                json_paths_or_exit(&matches, "output"),
+            )
+        } else {
+            (
+                subcommand_fields_or_exit(&matches, "field"),
+                subcommand_fields_or_exit(&matches, "with-nth"),
+                subcommand_fields_or_exit(&matches, "output"),
+            )
         };
 
+        let benchmark   = matches!(matches.subcommand_name(), Some("benchmark"));
 
         Self {
             lines,
@@ -37,9 +108,29 @@ impl Config {
             show_scores,
             query,
             delimiter,
-            field,
-            output,
-            benchmark
+            format,
+            search_fields,
+            display_fields,
+            output_fields,
+            benchmark,
+            max_length,
+            profile,
+            exact,
+            regex,
+            typos,
+            bindings,
+            walker,
+            source,
+            header,
+            header_lines,
+            layout,
+            cycle,
+            mouse,
+            vi,
+            color,
+            ansi,
+            ansi_output,
+            keep_right,
         }
     }
 
@@ -79,6 +170,189 @@ impl Config {
             .takes_value(true)
     }
 
+    fn max_length_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("max-length")
+            .long("max-length")
+            .value_name("LENGTH")
+            .help("Choices longer than this are scored approximately")
+            .takes_value(true)
+            .default_value("1024")
+    }
+
+    fn scheme_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("scheme")
+            .long("scheme")
+            .value_name("SCHEME")
+            .help("Scoring scheme to use [default: path]")
+            .takes_value(true)
+            .possible_values(&ScoringProfile::NAMES)
+    }
+
+    fn exact_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("exact")
+            .short("e")
+            .long("exact")
+            .help("Match the query as a contiguous substring")
+    }
+
+    fn regex_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("regex")
+            .short("r")
+            .long("regex")
+            .help("Match the query as a regular expression")
+            .conflicts_with("exact")
+    }
+
+    fn typos_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("typos")
+            .long("typos")
+            .value_name("TYPOS")
+            .help("Allow up to TYPOS mistyped, extra or transposed characters")
+            .takes_value(true)
+            .default_value("0")
+    }
+
+    fn bind_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("bind")
+            .long("bind")
+            .value_name("KEY:ACTION")
+            .help("Bind keys to actions, e.g. ctrl-e:toggle-exact")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+    }
+
+    fn no_cycle_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("no-cycle")
+            .long("no-cycle")
+            .help("Stop at the first and last choice instead of wrapping around")
+    }
+
+    fn mouse_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("mouse")
+            .long("mouse")
+            .help("Click to select a choice, double click to accept it and scroll with the wheel")
+    }
+
+    fn vi_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("vi")
+            .long("vi")
+            .help("Edit the query like vi, with Esc switching to normal mode. Abort with ctrl-c \
+                   or a key bound to abort")
+    }
+
+    fn color_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("color")
+            .long("color")
+            .help("Colors as a theme (dark or light) followed by element:color pairs, \
+                   e.g. light,hl:red,pointer:#303030. Elements are hl, hl+, prompt, \
+                   pointer, header, score and label")
+            .takes_value(true)
+            .default_value("dark")
+    }
+
+    fn no_color_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("no-color")
+            .long("no-color")
+            .help("Don't use colors, also set by NO_COLOR")
+    }
+
+    fn ansi_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("ansi")
+            .long("ansi")
+            .help("Keep the colors of input with ANSI escape codes, matching and printing it without them")
+    }
+
+    fn ansi_output_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("ansi-output")
+            .long("ansi-output")
+            .help("Like --ansi, but print the selection with its escape codes")
+    }
+
+    fn keep_right_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("keep-right")
+            .long("keep-right")
+            .help("Show the end of choices that are too long to fit, as long as their matches can be seen")
+    }
+
+    fn header_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("header")
+            .long("header")
+            .value_name("TEXT")
+            .help("Text to show above the choices")
+            .takes_value(true)
+    }
+
+    fn header_lines_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("header-lines")
+            .long("header-lines")
+            .value_name("N")
+            .help("Show the first N input lines above the choices instead of matching them")
+            .takes_value(true)
+            .default_value("0")
+    }
+
+    fn layout_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("layout")
+            .long("layout")
+            .value_name("LAYOUT")
+            .help("Where the prompt goes and which way the choices run")
+            .takes_value(true)
+            .possible_values(&Layout::NAMES)
+            .default_value("default")
+    }
+
+    fn walker_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("walker")
+            .long("walker")
+            .value_name("OPTIONS")
+            .help("What to list when stdin is a terminal, any of file, dir, hidden and follow")
+            .takes_value(true)
+            .default_value("file,follow")
+    }
+
+    fn source_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("source")
+            .long("source")
+            .value_name("COMMAND")
+            .help("Run COMMAND for the choices instead of reading stdin, {q} is replaced by the query")
+            .takes_value(true)
+            .conflicts_with("json")
+    }
+
+    fn json_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("json")
+            .long("json")
+            .help("Parse each line as a JSON object")
+    }
+
+    fn search_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("search")
+            .long("search")
+            .value_name("PATHS")
+            .help("JSON values to be matched, e.g. '.name .tags.0' [default: .]")
+            .takes_value(true)
+            .requires("json")
+    }
+
+    fn display_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("display")
+            .long("display")
+            .value_name("PATHS")
+            .help("JSON values to be displayed [default: PATHS]")
+            .takes_value(true)
+            .requires("json")
+    }
+
+    fn json_output_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("output")
+            .long("output")
+            .value_name("PATHS")
+            .help("JSON values to be returned once selected [default: PATHS]")
+            .takes_value(true)
+            .requires("json")
+    }
+
     fn delimiter_arg<'a>() -> Arg<'a, 'a> {
         Arg::with_name("delimiter")
             .short("d")
@@ -86,17 +360,35 @@ impl Config {
             .value_name("DELIMITER")
             .help("Use to split the line into fields")
             .takes_value(true)
-            .required(true)
+            .required_unless("format")
+            .conflicts_with("format")
+    }
+
+    fn format_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("format")
+            .long("format")
+            .value_name("FORMAT")
+            .help("Parses the input as records with a header row, allowing fields to be picked by name")
+            .takes_value(true)
+            .possible_values(&["csv", "tsv"])
     }
 
     fn field_arg<'a>() -> Arg<'a, 'a> {
         Arg::with_name("field")
             .short("f")
             .long("field")
-            .value_name("FIELD")
-            .help("Field to be matched")
+            .alias("nth")
+            .value_name("FIELDS")
+            .help("Fields to be matched, e.g. 1,3..5 or -1 for the last [default: all]")
+            .takes_value(true)
+    }
+
+    fn with_nth_arg<'a>() -> Arg<'a, 'a> {
+        Arg::with_name("with-nth")
+            .long("with-nth")
+            .value_name("FIELDS")
+            .help("Fields to be displayed [default: FIELDS]")
             .takes_value(true)
-            .required(true)
     }
 
     fn output_arg<'a>() -> Arg<'a, 'a> {
@@ -104,7 +396,7 @@ impl Config {
             .short("o")
             .long("output")
             .value_name("OUTPUT")
-            .help("Field to be returned once selected [default: FIELD]")
+            .help("Fields to be returned once selected [default: FIELDS]")
             .takes_value(true)
     }
 
@@ -117,6 +409,29 @@ impl Config {
             .arg(Self::prompt_arg())
             .arg(Self::query_arg())
             .arg(Self::show_scores_arg())
+            .arg(Self::max_length_arg())
+            .arg(Self::scheme_arg())
+            .arg(Self::exact_arg())
+            .arg(Self::regex_arg())
+            .arg(Self::typos_arg())
+            .arg(Self::bind_arg())
+            .arg(Self::layout_arg())
+            .arg(Self::no_cycle_arg())
+            .arg(Self::mouse_arg())
+            .arg(Self::vi_arg())
+            .arg(Self::color_arg())
+            .arg(Self::no_color_arg())
+            .arg(Self::ansi_arg())
+            .arg(Self::ansi_output_arg())
+            .arg(Self::keep_right_arg())
+            .arg(Self::header_arg())
+            .arg(Self::header_lines_arg())
+            .arg(Self::walker_arg())
+            .arg(Self::source_arg())
+            .arg(Self::json_arg())
+            .arg(Self::search_arg())
+            .arg(Self::display_arg())
+            .arg(Self::json_output_arg())
             .subcommand(Self::split_subcommand())
             .subcommand(Self::benchmark_subcommand())
     }
@@ -126,7 +441,9 @@ impl Config {
             .about("Splits lines into fields")
             .setting(AppSettings::DisableVersion)
             .arg(Self::delimiter_arg())
+            .arg(Self::format_arg())
             .arg(Self::field_arg())
+            .arg(Self::with_nth_arg())
             .arg(Self::output_arg())
     }
 
@@ -138,6 +455,122 @@ impl Config {
     }
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            lines: 10,
+            prompt: "❯ ".to_string(),
+            show_scores: false,
+            query: None,
+            delimiter: None,
+            format: None,
+            search_fields: None,
+            display_fields: None,
+            output_fields: None,
+            benchmark: false,
+            max_length: 1024,
+            profile: ScoringProfile::default(),
+            exact: false,
+            regex: false,
+            typos: 0,
+            bindings: vec![],
+            walker: WalkerOptions { files: true, dirs: false, hidden: false, follow: true },
+            source: None,
+            header: vec![],
+            header_lines: 0,
+            layout: Layout::Default,
+            cycle: true,
+            mouse: false,
+            vi: false,
+            color: Theme::default(),
+            ansi: false,
+            ansi_output: false,
+            keep_right: false,
+        }
+    }
+}
+
+// Reads `key = value` lines from $FOZZIE_CONFIG, falling back to
+// $XDG_CONFIG_HOME/fozzie/config and then ~/.config/fozzie/config.
+fn read_config_file() -> Vec<(String, String)> {
+    let path = if let Ok(path) = std::env::var("FOZZIE_CONFIG") {
+        std::path::PathBuf::from(path)
+    } else if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        std::path::PathBuf::from(dir).join("fozzie").join("config")
+    } else if let Ok(dir) = std::env::var("HOME") {
+        std::path::PathBuf::from(dir).join(".config").join("fozzie").join("config")
+    } else {
+        return vec![];
+    };
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return vec![],
+    };
+
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| match line.split_once('=') {
+            Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+            None => Error::with_description(
+                &format!("Invalid setting on line {} of {}", i + 1, path.display()),
+                clap::ErrorKind::InvalidValue
+            ).exit(),
+        })
+        .collect()
+}
+
+fn parse_profile(matches: &ArgMatches, settings: &[(String, String)]) -> ScoringProfile {
+    let scheme = if matches.is_present("scheme") {
+        value_t_or_exit!(matches, "scheme", String)
+    } else {
+        settings
+            .iter()
+            .rev()
+            .find(|(key, _)| key == "scheme")
+            .map_or_else(|| "path".to_string(), |(_, value)| value.clone())
+    };
+
+    let mut profile = match ScoringProfile::from_name(&scheme) {
+        Some(profile) => profile,
+        None => Error::value_validation_auto(format!("Unknown scheme '{}'", scheme)).exit(),
+    };
+
+    settings.iter().filter(|(key, _)| key != "scheme").for_each(|(key, value)| {
+        let weight = match value.parse::<f32>() {
+            Ok(weight) => weight,
+            Err(_) => Error::value_validation_auto(
+                format!("The setting '{}' must be a number", key)
+            ).exit(),
+        };
+
+        if let Err(message) = profile.set(key, weight) {
+            Error::value_validation_auto(message).exit();
+        }
+    });
+
+    profile
+}
+
+// Colors are left off when asked to and on terminals that don't have them,
+// RGB values are only sent as they are to terminals that say they take them.
+fn parse_color(matches: &ArgMatches) -> Theme {
+    let mut theme = match Theme::parse(matches.value_of("color").unwrap()) {
+        Ok(theme) => theme,
+        Err(message) => Error::value_validation_auto(message).exit(),
+    };
+
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let dumb = std::env::var("TERM").map_or(true, |term| term == "dumb");
+    theme.enabled = !matches.is_present("no-color") && !no_color && !dumb;
+    theme.truecolor = std::env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit");
+
+    theme
+}
+
 fn parse_query(matches: &ArgMatches) -> Option<String> {
     if matches.is_present("query") {
         Some(value_t_or_exit!(matches, "query", String))
@@ -152,31 +585,76 @@ fn parse_query(matches: &ArgMatches) -> Option<String> {
     }
 }
 
-fn optional_usize_value_or_exit(matches: &ArgMatches, field: &str) -> Option<usize> {
+fn parse_bindings(matches: &ArgMatches) -> Vec<String> {
+    match matches.values_of("bind") {
+        Some(values) => values.map(String::from).collect(),
+        None => vec![],
+    }
+}
+
+fn parse_walker(matches: &ArgMatches) -> WalkerOptions {
+    let mut options = WalkerOptions { files: false, dirs: false, hidden: false, follow: false };
+
+    for option in value_t_or_exit!(matches, "walker", String).split(',') {
+        match option.trim() {
+            "file" => options.files = true,
+            "dir" => options.dirs = true,
+            "hidden" => options.hidden = true,
+            "follow" => options.follow = true,
+            option => Error::value_validation_auto(format!("Unknown walker option '{}'", option)).exit(),
+        }
+    }
+
+    options
+}
+
+fn optional_fields_or_exit(matches: &ArgMatches, field: &str) -> Option<Vec<FieldRange>> {
     if matches.is_present(field) {
-        Some(value_t_or_exit!(matches, field, usize))
+        let spec = value_t_or_exit!(matches, field, String);
+
+        match crate::fields::parse(&spec) {
+            Ok(ranges) if !matches.is_present("format") && ranges.iter().any(FieldRange::is_name) => {
+                Error::value_validation_auto(format!("Invalid field list '{}', field names need --format", spec)).exit()
+            },
+            Ok(ranges) => Some(ranges),
+            Err(message) => Error::value_validation_auto(message).exit(),
+        }
     } else {
         None
     }
 }
 
-fn subcommand_usize_value_or_exit(matches: &ArgMatches, field: &str) -> Option<usize> {
+// Paths are written jq style, `.` being the whole object and `.a.0` the first
+// element of the `a` array. They become named fields, resolved per line.
+fn json_paths_or_exit(matches: &ArgMatches, field: &str) -> Option<Vec<FieldRange>> {
+    let spec = matches.value_of(field)?;
+
+    match spec.split_whitespace().find(|path| !path.starts_with('.')) {
+        Some(path) => Error::value_validation_auto(format!("Invalid path '{}', paths start with '.'", path)).exit(),
+        None if spec.trim().is_empty() => Error::value_validation_auto(format!("The argument '{}' needs at least one path", field)).exit(),
+        None => Some(spec.split_whitespace().map(|path| FieldRange::Name(path.to_string())).collect()),
+    }
+}
+
+fn subcommand_fields_or_exit(matches: &ArgMatches, field: &str) -> Option<Vec<FieldRange>> {
     if let Some(matches) = matches.subcommand_matches("split") {
-        optional_usize_value_or_exit(matches, field)
+        optional_fields_or_exit(matches, field)
     } else {
         None
     }
 }
 
-fn parse_lines(matches: &ArgMatches) -> usize {
+fn parse_lines(matches: &ArgMatches, fixed: usize) -> usize {
     let lines = value_t_or_exit!(matches, "lines", usize);
 
     if lines < 1 {
         Error::value_validation_auto(format!("The argument '{}' must be greater than 0", "lines")).exit();
     }
     if let Some((Width(_w), Height(h))) = terminal_size() {
-        if usize::from(h) <= lines {
-            Error::value_validation_auto(format!("The argument '{}' must be less than {}", "lines", h)).exit();
+        if usize::from(h) <= lines + fixed {
+            Error::value_validation_auto(
+                format!("The argument '{}' must be less than {}", "lines", usize::from(h).saturating_sub(fixed))
+            ).exit();
         }
     }
 
@@ -184,11 +662,33 @@ fn parse_lines(matches: &ArgMatches) -> usize {
 }
 
 fn parse_delimiter(matches: &ArgMatches) -> Option<Regex> {
-    if let Some(matches) = matches.subcommand_matches("split") {
-        let delimiter = value_t_or_exit!(matches, "delimiter", String);
+    match matches.subcommand_matches("split") {
+        Some(matches) if matches.is_present("delimiter") => {
+            let delimiter = value_t_or_exit!(matches, "delimiter", String);
 
-        Some(Regex::new(&delimiter).unwrap())
-    } else {
-        None
+            Some(Regex::new(&delimiter).unwrap())
+        },
+        _ => None,
+    }
+}
+
+fn parse_format(matches: &ArgMatches) -> Option<Format> {
+    if matches.is_present("json") {
+        if matches.subcommand_matches("split").is_some() {
+            Error::with_description("The argument '--json' cannot be used with 'split'", clap::ErrorKind::ArgumentConflict).exit();
+        }
+        return Some(Format::Json);
+    }
+
+    let format = matches.subcommand_matches("split").and_then(|matches| matches.value_of("format"));
+    // Lines from the source command are matched as they come, not parsed as records.
+    if format.is_some() && matches.is_present("source") {
+        Error::with_description("The argument '--source' cannot be used with '--format'", clap::ErrorKind::ArgumentConflict).exit();
+    }
+
+    match format {
+        Some("csv") => Some(Format::Csv),
+        Some("tsv") => Some(Format::Tsv),
+        _ => None,
     }
 }
@@ -6,12 +6,12 @@ pub fn up(n: usize) -> String {
     format!("\x1B[{}A", n)
 }
 
-pub fn left() -> &'static str {
-    "\x1B[1D"
+pub fn left(n: usize) -> String {
+    format!("\x1B[{}D", n)
 }
 
-pub fn right() -> &'static str {
-    "\x1B[1C"
+pub fn right(n: usize) -> String {
+    format!("\x1B[{}C", n)
 }
 
 pub fn clear_line() -> &'static str {
@@ -34,6 +34,39 @@ pub fn nowrap(test: &str) -> String {
     format!("\x1B[?7l{}\x1B[?7h", test)
 }
 
+// Reports clicks and the wheel in the SGR encoding, which isn't limited to
+// 223 rows and columns.
+pub fn enable_mouse() -> &'static str {
+    "\x1B[?1000h\x1B[?1006h"
+}
+
+pub fn disable_mouse() -> &'static str {
+    "\x1B[?1006l\x1B[?1000l"
+}
+
+pub fn report_position() -> &'static str {
+    "\x1B[6n"
+}
+
+// Reads the row out of a position report, which looks like `ESC [ row ; col R`.
+pub fn parse_row(report: &str) -> Option<usize> {
+    let start = report.rfind("\x1B[")? + 2;
+    let (row, _) = report[start..].trim_end_matches('R').split_once(';')?;
+
+    row.parse().ok()
+}
+
+// Where the position report that `input` ends with starts, telling it apart
+// from any keys that were read before it.
+pub fn find_report(input: &[u8]) -> Option<usize> {
+    let start = input.windows(2).rposition(|pair| pair == b"\x1B[")?;
+    let report = std::str::from_utf8(&input[start + 2..]).ok()?.strip_suffix('R')?;
+    let (row, col) = report.split_once(';')?;
+    let number = |text: &str| !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit());
+
+    (number(row) && number(col)).then_some(start)
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -46,12 +79,13 @@ mod tests {
 
     #[test]
     fn test_left() {
-        assert_eq!("\x1B[1D", left());
+        assert_eq!("\x1B[1D", left(1));
     }
 
     #[test]
     fn test_right() {
-        assert_eq!("\x1B[1C", right());
+        assert_eq!("\x1B[1C", right(1));
+        assert_eq!("\x1B[2C", right(2));
     }
 
     #[test]
@@ -74,6 +108,22 @@ mod tests {
         assert_eq!("\x1B7", save_position());
     }
 
+    #[test]
+    fn test_parse_row() {
+        assert_eq!(Some(12), parse_row("\x1B[12;1R"));
+        assert_eq!(Some(3), parse_row("x\x1B[3;40R"));
+        assert_eq!(None, parse_row("\x1B[R"));
+    }
+
+    #[test]
+    fn test_find_report() {
+        assert_eq!(Some(0), find_report(b"\x1B[12;1R"));
+        assert_eq!(Some(2), find_report(b"aR\x1B[3;40R"));
+        assert_eq!(None, find_report(b"aR"));
+        assert_eq!(None, find_report(b"\x1B[3;R"));
+        assert_eq!(None, find_report(b"\x1B[<0;3;4M"));
+    }
+
     #[test]
     fn test_restore_position() {
         assert_eq!("\x1B8", restore_position());
@@ -0,0 +1,107 @@
+use std::io::Read;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::event::{self, Key, MouseEvent};
+use termion::input::TermRead;
+
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+pub enum Event {
+    Key(Key),
+    Mouse(MouseEvent),
+    // Lines from a source, tagged with the run of the source they came from so
+    // output from a run that has since been replaced can be ignored.
+    Choices(usize, Vec<String>),
+    // Sent while a source is running but has nothing new.
+    Tick(usize),
+    Done(usize),
+}
+
+// Keys are read on their own thread so the loop can wait on other sources too.
+// The first key, and each one after it, is only read once the loop asks for
+// another event, so until then and while a key is being handled nothing else
+// reads from the terminal.
+pub struct Events {
+    receiver: Receiver<Event>,
+    resume: SyncSender<()>,
+    handling_input: bool,
+}
+
+impl Events {
+    pub fn new(tty: impl Read + Send + 'static) -> (Events, Sender<Event>) {
+        let (sender, receiver) = mpsc::channel();
+        let (resume, resumed) = mpsc::sync_channel(1);
+        let keys = sender.clone();
+
+        thread::spawn(move || {
+            if resumed.recv().is_err() {
+                return;
+            }
+
+            for input in tty.events() {
+                let event = match input {
+                    Ok(event::Event::Key(key)) => Event::Key(key),
+                    Ok(event::Event::Mouse(mouse)) => Event::Mouse(mouse),
+                    Ok(event::Event::Unsupported(_)) => continue,
+                    Err(_) => break,
+                };
+                if keys.send(event).is_err() || resumed.recv().is_err() {
+                    break;
+                }
+            }
+        });
+
+        (Events { receiver, resume, handling_input: true }, sender)
+    }
+}
+
+impl Iterator for Events {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if self.handling_input {
+            self.handling_input = false;
+            self.resume.send(()).ok()?;
+        }
+
+        let event = self.receiver.recv().ok()?;
+        self.handling_input = matches!(event, Event::Key(_) | Event::Mouse(_));
+
+        Some(event)
+    }
+}
+
+// Groups lines into batches so the choices aren't redrawn for every line,
+// finishing with Done once the sending side hangs up.
+pub fn batch(lines: Receiver<String>, generation: usize, events: Sender<Event>) {
+    thread::spawn(move || {
+        let mut batch = vec![];
+        let mut sent_at = Instant::now();
+
+        loop {
+            match lines.recv_timeout(BATCH_INTERVAL) {
+                Ok(line) => batch.push(line),
+                Err(RecvTimeoutError::Timeout) if batch.is_empty() => {
+                    if events.send(Event::Tick(generation)).is_err() {
+                        return;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if !batch.is_empty() && sent_at.elapsed() >= BATCH_INTERVAL {
+                if events.send(Event::Choices(generation, std::mem::take(&mut batch))).is_err() {
+                    return;
+                }
+                sent_at = Instant::now();
+            }
+        }
+
+        if !batch.is_empty() {
+            let _ = events.send(Event::Choices(generation, batch));
+        }
+        let _ = events.send(Event::Done(generation));
+    });
+}
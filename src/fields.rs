@@ -0,0 +1,122 @@
+// A single entry of a field list such as `1,3..5,-1`. Indexes start at 0,
+// negative indexes count back from the last field and ranges are inclusive.
+// With a header row, fields can also be picked by name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldRange {
+    Range { start: Option<isize>, end: Option<isize> },
+    Name(String),
+}
+
+impl FieldRange {
+    pub fn is_name(&self) -> bool {
+        matches!(self, FieldRange::Name(_))
+    }
+}
+
+pub fn parse(spec: &str) -> Result<Vec<FieldRange>, String> {
+    spec.split(',')
+        .map(|part| {
+            let part = part.trim();
+            let (start, end) = match part.split_once("..") {
+                Some((start, end)) => (parse_index(start, part)?, parse_index(end, part)?),
+                None if part.is_empty() => return Err(format!("Invalid field '{}'", part)),
+                None => match parse_index(part, part) {
+                    Ok(index) => (index, index),
+                    Err(_) => return Ok(FieldRange::Name(part.to_string())),
+                },
+            };
+
+            Ok(FieldRange::Range { start, end })
+        })
+        .collect()
+}
+
+fn parse_index(index: &str, part: &str) -> Result<Option<isize>, String> {
+    if index.is_empty() {
+        Ok(None)
+    } else {
+        index.parse::<isize>().map(Some).map_err(|_| format!("Invalid field '{}'", part))
+    }
+}
+
+// Checks that every named field is one of the header's columns.
+pub fn validate(ranges: &[FieldRange], header: &[String]) -> Result<(), String> {
+    match ranges.iter().find(|range| match range {
+        FieldRange::Name(name) => !header.contains(name),
+        _ => false,
+    }) {
+        Some(FieldRange::Name(name)) => Err(format!("Unknown field '{}', expected one of: {}", name, header.join(", "))),
+        _ => Ok(()),
+    }
+}
+
+// Resolves a field list against a line with `count` fields. Fields that don't
+// exist on the line are skipped.
+pub fn select(ranges: &[FieldRange], count: usize, header: &[String]) -> Vec<usize> {
+    let resolve = |index: isize| {
+        if index < 0 { count as isize + index } else { index }
+    };
+    let mut selected: Vec<usize> = vec![];
+
+    ranges.iter().for_each(|range| {
+        let (start, end) = match range {
+            FieldRange::Range { start, end } => (
+                start.map_or(0, resolve).max(0),
+                end.map_or(count as isize - 1, resolve).min(count as isize - 1),
+            ),
+            FieldRange::Name(name) => match header.iter().position(|column| column == name) {
+                Some(index) if index < count => (index as isize, index as isize),
+                _ => return,
+            },
+        };
+
+        (start..=end).for_each(|index| {
+            if !selected.contains(&(index as usize)) {
+                selected.push(index as usize);
+            }
+        });
+    });
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Ok(vec![
+                FieldRange::Range { start: Some(1), end: Some(1) },
+                FieldRange::Range { start: Some(3), end: Some(5) },
+                FieldRange::Range { start: Some(-1), end: Some(-1) },
+                FieldRange::Range { start: None, end: Some(2) },
+                FieldRange::Name("name".to_string()),
+            ]),
+            parse("1,3..5,-1,..2,name")
+        );
+        assert!(parse("1..b").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(vec![1, 3, 4], select(&parse("1,3..5").unwrap(), 5, &[]));
+        assert_eq!(vec![4], select(&parse("-1").unwrap(), 5, &[]));
+        assert_eq!(vec![2, 3, 4], select(&parse("2..").unwrap(), 5, &[]));
+        assert_eq!(vec![0, 1], select(&parse("..1,0").unwrap(), 5, &[]));
+        assert!(select(&parse("7").unwrap(), 5, &[]).is_empty());
+        assert!(select(&parse("-7").unwrap(), 5, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_select_names() {
+        let header = vec!["id".to_string(), "name".to_string(), "email".to_string()];
+
+        assert_eq!(vec![2, 0], select(&parse("email,id").unwrap(), 3, &header));
+        assert!(select(&parse("email").unwrap(), 2, &header).is_empty());
+        assert!(validate(&parse("name,0").unwrap(), &header).is_ok());
+        assert!(validate(&parse("phone").unwrap(), &header).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::choice::Choice;
+use crate::config::{Config, Format};
+use crate::fields::{self, FieldRange};
+use rayon::prelude::*;
+use serde_json::Value;
+
+// Choices parsed from the input, along with any lines to be shown above them.
+pub struct Input<'a> {
+    pub choices: Vec<Choice<'a>>,
+    pub header: Vec<String>,
+    pub skipped: usize,
+}
+
+pub fn parse<'a>(buffer: &'a str, config: &Config) -> Result<Input<'a>, String> {
+    match config.format {
+        Some(Format::Json) => Ok(parse_json(buffer, config)),
+        Some(format) => parse_records(buffer, format, config),
+        None => Ok(Input {
+            choices: buffer.par_lines().map(|choice| Choice::new(choice, config)).collect(),
+            header: vec![],
+            skipped: 0,
+        }),
+    }
+}
+
+// The first record names the fields and is displayed as a fixed line, using the
+// same fields as the choices.
+fn parse_records<'a>(buffer: &'a str, format: Format, config: &Config) -> Result<Input<'a>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(if format == Format::Tsv { b'\t' } else { b',' })
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(buffer.as_bytes());
+
+    let mut records = reader.records().map(|record| match record {
+        Ok(record) => Ok(record.iter().map(String::from).collect::<Vec<String>>()),
+        Err(error) => Err(error.to_string()),
+    });
+
+    let header = match records.next() {
+        Some(header) => header?,
+        None => return Ok(Input { choices: vec![], header: vec![], skipped: 0 }),
+    };
+
+    for ranges in [&config.search_fields, &config.display_fields, &config.output_fields].iter().copied().flatten() {
+        fields::validate(ranges, &header)?;
+    }
+
+    let records = records.collect::<Result<Vec<Vec<String>>, String>>()?;
+    let delimiter = if format == Format::Tsv { "\t" } else { "," };
+
+    Ok(Input {
+        choices: records.par_iter().map(|record| Choice::from_record(record, &header, delimiter, config)).collect(),
+        header: vec![Choice::from_record(&header, &header, delimiter, config).display.into_owned()],
+        skipped: 0,
+    })
+}
+
+// Every path used by the field lists becomes a column, so a line turns into a
+// record of the values found at those paths.
+fn parse_json<'a>(buffer: &'a str, config: &Config) -> Input<'a> {
+    let mut paths: Vec<String> = vec![];
+    for range in [&config.search_fields, &config.display_fields, &config.output_fields].iter().copied().flatten().flatten() {
+        if let FieldRange::Name(path) = range {
+            if !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+    }
+    let pointers: Vec<String> = paths.iter().map(|path| pointer(path)).collect();
+
+    let parsed: Vec<Option<Choice>> = buffer
+        .par_lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let value = serde_json::from_str::<Value>(line).ok()?;
+            let record: Vec<String> = pointers.iter().map(|pointer| render(value.pointer(pointer))).collect();
+
+            Some(Choice::from_record(&record, &paths, "\t", config))
+        })
+        .collect();
+
+    let skipped = parsed.iter().filter(|choice| choice.is_none()).count();
+
+    Input {
+        choices: parsed.into_iter().flatten().collect(),
+        header: vec![],
+        skipped,
+    }
+}
+
+// `.a.0` becomes the JSON pointer `/a/0`.
+fn pointer(path: &str) -> String {
+    path.split('.')
+        .filter(|key| !key.is_empty())
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+fn render(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(string)) => string.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lines() {
+        let input = parse("foo\nbar\n", &Config::default()).unwrap();
+
+        assert_eq!(2, input.choices.len());
+        assert!(input.header.is_empty());
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let config = Config {
+            format: Some(Format::Csv),
+            search_fields: Some(fields::parse("name").unwrap()),
+            output_fields: Some(fields::parse("id").unwrap()),
+            ..Config::default()
+        };
+        let input = parse("id,name\n1,\"Smith, Jane\"\n2,\"say \"\"hi\"\"\nthere\"\n", &config).unwrap();
+
+        assert_eq!(vec!["name".to_string()], input.header);
+        assert_eq!(2, input.choices.len());
+        assert_eq!("Smith, Jane", input.choices[0].searchable);
+        assert_eq!("1", input.choices[0].returnable);
+        assert_eq!("say \"hi\" there", input.choices[1].searchable);
+    }
+
+    #[test]
+    fn test_parse_csv_multiline_field() {
+        let config = Config { format: Some(Format::Csv), ..Config::default() };
+        let input = parse("id,note\n1,\"first\nsecond\"\n", &config).unwrap();
+
+        assert_eq!("1 | first second", input.choices[0].display);
+        assert_eq!("1,first\nsecond", input.choices[0].returnable);
+    }
+
+    #[test]
+    fn test_parse_tsv() {
+        let config = Config { format: Some(Format::Tsv), ..Config::default() };
+        let input = parse("a\tb\nc\td\n", &config).unwrap();
+
+        assert_eq!(vec!["a | b".to_string()], input.header);
+        assert_eq!("c | d", input.choices[0].display);
+        assert_eq!("c\td", input.choices[0].returnable);
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let config = Config {
+            format: Some(Format::Json),
+            search_fields: Some(vec![FieldRange::Name(".name".to_string())]),
+            display_fields: Some(vec![FieldRange::Name(".name".to_string()), FieldRange::Name(".tags.0".to_string())]),
+            output_fields: Some(vec![FieldRange::Name(".id".to_string())]),
+            ..Config::default()
+        };
+        let buffer = "{\"id\": 7, \"name\": \"foo\", \"tags\": [\"bar\"]}\nnot json\n\n{\"id\": \"x\"}\n";
+        let input = parse(buffer, &config).unwrap();
+
+        assert_eq!(1, input.skipped);
+        assert_eq!(2, input.choices.len());
+        assert_eq!("foo", input.choices[0].searchable);
+        assert_eq!("foo | bar", input.choices[0].display);
+        assert_eq!("7", input.choices[0].returnable);
+        assert_eq!("", input.choices[1].searchable);
+        assert_eq!("x", input.choices[1].returnable);
+    }
+
+    #[test]
+    fn test_parse_json_whole_object() {
+        let config = Config {
+            format: Some(Format::Json),
+            search_fields: Some(vec![FieldRange::Name(".".to_string())]),
+            ..Config::default()
+        };
+        let input = parse("{\"a\":1}\n", &config).unwrap();
+
+        assert_eq!("{\"a\":1}", input.choices[0].returnable);
+    }
+
+    #[test]
+    fn test_parse_unknown_field() {
+        let config = Config {
+            format: Some(Format::Csv),
+            search_fields: Some(fields::parse("phone").unwrap()),
+            ..Config::default()
+        };
+
+        assert!(parse("id,name\n1,Jane\n", &config).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use termion::event::Key;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Accept,
+    Abort,
+    ReplaceQuery,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    BackwardChar,
+    ForwardChar,
+    BeginningOfLine,
+    EndOfLine,
+    BackwardWord,
+    ForwardWord,
+    BackwardDeleteChar,
+    BackwardKillWord,
+    DeleteChar,
+    KillWord,
+    KillLine,
+    UnixLineDiscard,
+    UnixWordRubout,
+    Yank,
+    YankPop,
+    Undo,
+    Redo,
+    ClearQuery,
+    ToggleExact,
+    ToggleRegex,
+    Reload,
+    ToggleMark,
+    Jump,
+    JumpAccept,
+    ViNormalMode,
+    ViInsert,
+    ViAppend,
+    ViInsertAtStart,
+    ViAppendAtEnd,
+    ViForwardWord,
+    ViBackwardWord,
+    ViEndOfWord,
+    ViDelete,
+    ViChange,
+    ViChangeToEnd,
+    Execute(String),
+    ExecuteSilent(String),
+}
+
+impl Action {
+    pub fn parse(name: &str) -> Result<Action, String> {
+        match name {
+            "accept" => Ok(Action::Accept),
+            "abort" => Ok(Action::Abort),
+            "replace-query" => Ok(Action::ReplaceQuery),
+            "up" => Ok(Action::Up),
+            "down" => Ok(Action::Down),
+            "page-up" => Ok(Action::PageUp),
+            "page-down" => Ok(Action::PageDown),
+            "first" => Ok(Action::First),
+            "last" => Ok(Action::Last),
+            "backward-char" => Ok(Action::BackwardChar),
+            "forward-char" => Ok(Action::ForwardChar),
+            "beginning-of-line" => Ok(Action::BeginningOfLine),
+            "end-of-line" => Ok(Action::EndOfLine),
+            "backward-word" => Ok(Action::BackwardWord),
+            "forward-word" => Ok(Action::ForwardWord),
+            "backward-delete-char" => Ok(Action::BackwardDeleteChar),
+            "backward-kill-word" => Ok(Action::BackwardKillWord),
+            "delete-char" => Ok(Action::DeleteChar),
+            "kill-word" => Ok(Action::KillWord),
+            "kill-line" => Ok(Action::KillLine),
+            "unix-line-discard" => Ok(Action::UnixLineDiscard),
+            "unix-word-rubout" => Ok(Action::UnixWordRubout),
+            "yank" => Ok(Action::Yank),
+            "yank-pop" => Ok(Action::YankPop),
+            "undo" => Ok(Action::Undo),
+            "redo" => Ok(Action::Redo),
+            "clear-query" => Ok(Action::ClearQuery),
+            "toggle-exact" => Ok(Action::ToggleExact),
+            "toggle-regex" => Ok(Action::ToggleRegex),
+            "reload" => Ok(Action::Reload),
+            "toggle-mark" => Ok(Action::ToggleMark),
+            "jump" => Ok(Action::Jump),
+            "jump-accept" => Ok(Action::JumpAccept),
+            "vi-normal-mode" => Ok(Action::ViNormalMode),
+            "vi-insert" => Ok(Action::ViInsert),
+            "vi-append" => Ok(Action::ViAppend),
+            "vi-insert-at-start" => Ok(Action::ViInsertAtStart),
+            "vi-append-at-end" => Ok(Action::ViAppendAtEnd),
+            "vi-forward-word" => Ok(Action::ViForwardWord),
+            "vi-backward-word" => Ok(Action::ViBackwardWord),
+            "vi-end-of-word" => Ok(Action::ViEndOfWord),
+            "vi-delete" => Ok(Action::ViDelete),
+            "vi-change" => Ok(Action::ViChange),
+            "vi-change-to-end" => Ok(Action::ViChangeToEnd),
+            _ => {
+                if let Some(command) = argument(name, "execute-silent") {
+                    Ok(Action::ExecuteSilent(command.to_string()))
+                } else if let Some(command) = argument(name, "execute") {
+                    Ok(Action::Execute(command.to_string()))
+                } else {
+                    Err(format!("Unknown action '{}'", name))
+                }
+            }
+        }
+    }
+}
+
+pub struct Keymap {
+    bindings: HashMap<Key, Action>,
+}
+
+impl Keymap {
+    pub fn new(overrides: &[String]) -> Result<Keymap, String> {
+        let mut keymap = Self::default();
+
+        for binding in overrides {
+            for spec in split_bindings(binding).into_iter().filter(|spec| !spec.is_empty()) {
+                let (key, action) = match spec.split_once(':') {
+                    Some((key, action)) => (parse_key(key)?, Action::parse(action)?),
+                    None => return Err(format!("Invalid binding '{}', expected KEY:ACTION", spec)),
+                };
+                keymap.bindings.insert(key, action);
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    pub fn action(&self, key: &Key) -> Option<&Action> {
+        self.bindings.get(key)
+    }
+
+    // The keys of vi's normal mode. Keys it leaves unbound fall back to the
+    // insert mode keymap, except for characters, which do nothing.
+    pub fn vi_normal() -> Keymap {
+        let bindings = vec![
+            (Key::Esc, Action::ViNormalMode),
+            (Key::Char('h'), Action::BackwardChar),
+            (Key::Char('l'), Action::ForwardChar),
+            (Key::Char(' '), Action::ForwardChar),
+            (Key::Char('w'), Action::ViForwardWord),
+            (Key::Char('b'), Action::ViBackwardWord),
+            (Key::Char('e'), Action::ViEndOfWord),
+            (Key::Char('0'), Action::BeginningOfLine),
+            (Key::Char('^'), Action::BeginningOfLine),
+            (Key::Char('$'), Action::EndOfLine),
+            (Key::Char('x'), Action::DeleteChar),
+            (Key::Char('d'), Action::ViDelete),
+            (Key::Char('c'), Action::ViChange),
+            (Key::Char('D'), Action::KillLine),
+            (Key::Char('C'), Action::ViChangeToEnd),
+            (Key::Char('u'), Action::Undo),
+            (Key::Ctrl('r'), Action::Redo),
+            (Key::Char('j'), Action::Down),
+            (Key::Char('k'), Action::Up),
+            (Key::Char('i'), Action::ViInsert),
+            (Key::Char('a'), Action::ViAppend),
+            (Key::Char('I'), Action::ViInsertAtStart),
+            (Key::Char('A'), Action::ViAppendAtEnd),
+        ];
+
+        Keymap { bindings: bindings.into_iter().collect() }
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = vec![
+            (Key::Char('\n'), Action::Accept),
+            (Key::Esc, Action::Abort),
+            (Key::Ctrl('c'), Action::Abort),
+            (Key::Char('\t'), Action::ReplaceQuery),
+            (Key::Up, Action::Up),
+            (Key::Down, Action::Down),
+            (Key::PageUp, Action::PageUp),
+            (Key::PageDown, Action::PageDown),
+            (Key::Home, Action::First),
+            (Key::End, Action::Last),
+            (Key::Left, Action::BackwardChar),
+            (Key::Ctrl('b'), Action::BackwardChar),
+            (Key::Right, Action::ForwardChar),
+            (Key::Ctrl('f'), Action::ForwardChar),
+            (Key::Ctrl('a'), Action::BeginningOfLine),
+            (Key::Ctrl('e'), Action::EndOfLine),
+            (Key::Alt('b'), Action::BackwardWord),
+            (Key::Alt('f'), Action::ForwardWord),
+            (Key::Backspace, Action::BackwardDeleteChar),
+            (Key::Ctrl('h'), Action::BackwardDeleteChar),
+            (Key::Alt('\x7f'), Action::BackwardKillWord),
+            (Key::Ctrl('d'), Action::DeleteChar),
+            (Key::Alt('d'), Action::KillWord),
+            (Key::Ctrl('k'), Action::KillLine),
+            (Key::Ctrl('u'), Action::UnixLineDiscard),
+            (Key::Ctrl('w'), Action::UnixWordRubout),
+            (Key::Ctrl('y'), Action::Yank),
+            (Key::Alt('y'), Action::YankPop),
+            (Key::Ctrl('7'), Action::Undo),
+            (Key::Ctrl('z'), Action::Undo),
+            (Key::Alt('z'), Action::Redo),
+            (Key::Alt('e'), Action::ToggleExact),
+            (Key::Alt('r'), Action::ToggleRegex),
+        ];
+
+        Keymap { bindings: bindings.into_iter().collect() }
+    }
+}
+
+// The text between the parentheses of `name(...)`.
+fn argument<'n>(action: &'n str, name: &str) -> Option<&'n str> {
+    action.strip_prefix(name)?.strip_prefix('(')?.strip_suffix(')')
+}
+
+// Splits on commas outside of parentheses, so commands can contain them.
+fn split_bindings(bindings: &str) -> Vec<&str> {
+    let mut specs = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in bindings.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            ',' if depth == 0 => {
+                specs.push(&bindings[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    specs.push(&bindings[start..]);
+
+    specs
+}
+
+fn parse_key(name: &str) -> Result<Key, String> {
+    if let Some(rest) = name.strip_prefix("ctrl-") {
+        return match single_char(rest) {
+            // Sent as the same byte as ctrl-7 and read that way.
+            Some('_' | '/') => Ok(Key::Ctrl('7')),
+            Some(c) => Ok(Key::Ctrl(c)),
+            None => Err(format!("Unknown key '{}'", name)),
+        };
+    }
+    if let Some(rest) = name.strip_prefix("alt-") {
+        return match rest {
+            "bspace" | "backspace" => Ok(Key::Alt('\x7f')),
+            _ => match single_char(rest) {
+                Some(c) => Ok(Key::Alt(c)),
+                None => Err(format!("Unknown key '{}'", name)),
+            },
+        };
+    }
+
+    match name {
+        "enter" | "return" => Ok(Key::Char('\n')),
+        "tab" => Ok(Key::Char('\t')),
+        "space" => Ok(Key::Char(' ')),
+        "esc" => Ok(Key::Esc),
+        "up" => Ok(Key::Up),
+        "down" => Ok(Key::Down),
+        "left" => Ok(Key::Left),
+        "right" => Ok(Key::Right),
+        "bspace" | "backspace" => Ok(Key::Backspace),
+        "del" | "delete" => Ok(Key::Delete),
+        "home" => Ok(Key::Home),
+        "end" => Ok(Key::End),
+        "pgup" | "page-up" => Ok(Key::PageUp),
+        "pgdn" | "page-down" => Ok(Key::PageDown),
+        "btab" | "shift-tab" => Ok(Key::BackTab),
+        _ => match single_char(name) {
+            Some(c) => Ok(Key::Char(c)),
+            None => Err(format!("Unknown key '{}'", name)),
+        },
+    }
+}
+
+fn single_char(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let keymap = Keymap::default();
+
+        assert_eq!(Some(&Action::Accept), keymap.action(&Key::Char('\n')));
+        assert_eq!(Some(&Action::ToggleExact), keymap.action(&Key::Alt('e')));
+        assert_eq!(None, keymap.action(&Key::Char('a')));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let keymap = Keymap::new(&["ctrl-e:toggle-exact,alt-bspace:clear-query".to_string()]).unwrap();
+
+        assert_eq!(Some(&Action::ToggleExact), keymap.action(&Key::Ctrl('e')));
+        assert_eq!(Some(&Action::ClearQuery), keymap.action(&Key::Alt('\x7f')));
+        assert_eq!(Some(&Action::Accept), keymap.action(&Key::Char('\n')));
+    }
+
+    #[test]
+    fn test_execute_overrides() {
+        let keymap = Keymap::new(&["ctrl-o:execute(vim {}),ctrl-y:execute-silent(printf '%s,%s' {} {q})".to_string()]).unwrap();
+
+        assert_eq!(Some(&Action::Execute("vim {}".to_string())), keymap.action(&Key::Ctrl('o')));
+        assert_eq!(
+            Some(&Action::ExecuteSilent("printf '%s,%s' {} {q}".to_string())),
+            keymap.action(&Key::Ctrl('y'))
+        );
+        assert!(Keymap::new(&["ctrl-o:execute(vim {}".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_vi_normal() {
+        let keymap = Keymap::vi_normal();
+
+        assert_eq!(Some(&Action::ViForwardWord), keymap.action(&Key::Char('w')));
+        assert_eq!(Some(&Action::Down), keymap.action(&Key::Char('j')));
+        assert_eq!(None, keymap.action(&Key::Char('q')));
+        assert_eq!(Ok(Action::ViChange), Action::parse("vi-change"));
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        assert!(Keymap::new(&["ctrl-e".to_string()]).is_err());
+        assert!(Keymap::new(&["ctrl-:accept".to_string()]).is_err());
+        assert!(Keymap::new(&["ctrl-e:explode".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(Ok(Key::Ctrl('a')), parse_key("ctrl-a"));
+        assert_eq!(Ok(Key::Ctrl('7')), parse_key("ctrl-_"));
+        assert_eq!(Ok(Key::Alt('x')), parse_key("alt-x"));
+        assert_eq!(Ok(Key::PageDown), parse_key("pgdn"));
+        assert_eq!(Ok(Key::Char('q')), parse_key("q"));
+        assert!(parse_key("hyper-q").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::cursor;
+
+// Where the prompt sits in the inline area and which way the choices run.
+// Default puts the prompt on top with the best match right below it, reverse
+// puts the prompt at the bottom with the best match right above it and
+// reverse-list keeps the prompt on top but puts the best match last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    Default,
+    Reverse,
+    ReverseList,
+}
+
+impl Layout {
+    pub const NAMES: [&'static str; 3] = ["default", "reverse", "reverse-list"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Layout::Default),
+            "reverse" => Some(Layout::Reverse),
+            "reverse-list" => Some(Layout::ReverseList),
+            _ => None,
+        }
+    }
+
+    // Whether the best match is drawn below the others.
+    pub fn best_last(self) -> bool {
+        matches!(self, Layout::Reverse | Layout::ReverseList)
+    }
+
+    // Puts the header and status lines and the rows, best match first, in the
+    // order they are drawn from the top. `rows` is how many rows there is room
+    // for, reverse fills the space it doesn't use from the top.
+    pub fn arrange(self, fixed: Vec<String>, mut matches: Vec<String>, rows: usize) -> Vec<String> {
+        match self {
+            Layout::Default => fixed.into_iter().chain(matches).collect(),
+            Layout::ReverseList => {
+                matches.reverse();
+                fixed.into_iter().chain(matches).collect()
+            }
+            Layout::Reverse => {
+                matches.reverse();
+                let padding = vec![String::new(); rows.saturating_sub(matches.len())];
+                padding.into_iter().chain(matches).chain(fixed).collect()
+            }
+        }
+    }
+
+    // Which of the `rows` drawn matches, best first, is on the line `offset`
+    // lines below the prompt, with `fixed` header and status lines drawn
+    // between the prompt and the matches.
+    pub fn row_at(self, offset: isize, fixed: usize, rows: usize) -> Option<usize> {
+        let distance = match self {
+            Layout::Reverse => -offset,
+            _ => offset,
+        };
+        let row = usize::try_from(distance - 1).ok()?.checked_sub(fixed)?;
+
+        match self {
+            _ if row >= rows => None,
+            Layout::ReverseList => Some(rows - 1 - row),
+            _ => Some(row),
+        }
+    }
+
+    // Draws arranged lines for the first time, starting on the current line and
+    // leaving the cursor on the prompt's line. `reserved` lines are kept for
+    // the choices whether or not they are all used.
+    pub fn initial_draw(self, lines: &[String], reserved: usize) -> String {
+        match self {
+            Layout::Reverse => format!("\r{}\r\n", Self::cleared(lines).join("\r\n")),
+            _ => format!(
+                "\r\n{}{}\r{}",
+                lines.join("\n\r"),
+                "\n".repeat(reserved - lines.len().max(1)),
+                cursor::up(reserved)
+            ),
+        }
+    }
+
+    // Redraws arranged lines, leaving the cursor where it was on the prompt.
+    pub fn draw(self, lines: &[String], reserved: usize) -> String {
+        match self {
+            Layout::Reverse => format!(
+                "{}{}\r{}{}",
+                cursor::save_position(),
+                cursor::up(reserved),
+                Self::cleared(lines).join("\r\n"),
+                cursor::restore_position(),
+            ),
+            _ => format!(
+                "{}\r\n{}\r{}{}",
+                cursor::save_position(),
+                cursor::clear_screen_down(),
+                lines.join("\n\r"),
+                cursor::restore_position(),
+            ),
+        }
+    }
+
+    // Clears the whole inline area, prompt included.
+    pub fn clear(self, reserved: usize) -> String {
+        match self {
+            Layout::Reverse => format!("{}\r{}", cursor::up(reserved), cursor::clear_screen_down()),
+            _ => format!("\r{}", cursor::clear_screen_down()),
+        }
+    }
+
+    fn cleared(lines: &[String]) -> Vec<String> {
+        lines.iter().map(|line| format!("{}{}", cursor::clear_line(), line)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Some(Layout::Reverse), Layout::from_name("reverse"));
+        assert_eq!(None, Layout::from_name("sideways"));
+    }
+
+    #[test]
+    fn test_arrange() {
+        let fixed = vec!["status".to_string()];
+        let matches = vec!["best".to_string(), "worst".to_string()];
+
+        assert_eq!(vec!["status", "best", "worst"], Layout::Default.arrange(fixed.clone(), matches.clone(), 3));
+        assert_eq!(vec!["status", "worst", "best"], Layout::ReverseList.arrange(fixed.clone(), matches.clone(), 3));
+        assert_eq!(vec!["", "worst", "best", "status"], Layout::Reverse.arrange(fixed, matches, 3));
+    }
+
+    #[test]
+    fn test_row_at() {
+        assert_eq!(Some(0), Layout::Default.row_at(2, 1, 3));
+        assert_eq!(None, Layout::Default.row_at(1, 1, 3));
+        assert_eq!(None, Layout::Default.row_at(5, 1, 3));
+        assert_eq!(Some(2), Layout::ReverseList.row_at(2, 1, 3));
+        assert_eq!(Some(1), Layout::Reverse.row_at(-3, 1, 3));
+        assert_eq!(None, Layout::Reverse.row_at(2, 1, 3));
+    }
+
+    #[test]
+    fn test_reverse_draw() {
+        let lines = vec!["a".to_string(), "b".to_string()];
+
+        assert_eq!("\r\x1B[2Ka\r\n\x1B[2Kb\r\n", Layout::Reverse.initial_draw(&lines, 2));
+        assert_eq!("\x1B7\x1B[2A\r\x1B[2Ka\r\n\x1B[2Kb\x1B8", Layout::Reverse.draw(&lines, 2));
+        assert_eq!("\x1B[2A\r\x1B[J", Layout::Reverse.clear(2));
+    }
+
+    #[test]
+    fn test_default_draw() {
+        let lines = vec!["a".to_string()];
+
+        assert_eq!("\r\na\n\r\x1B[2A", Layout::Default.initial_draw(&lines, 2));
+        assert_eq!("\r\x1B[J", Layout::Default.clear(2));
+    }
+}
@@ -4,26 +4,39 @@ extern crate clap;
 extern crate terminal_size;
 extern crate test;
 
+pub mod ansi;
 pub mod bonus;
 pub mod choices;
 pub mod choice;
 pub mod color;
+pub mod command;
 pub mod config;
 pub mod cursor;
+pub mod events;
+pub mod fields;
+pub mod input;
+pub mod keymap;
+pub mod layout;
 pub mod matcher;
 pub mod matrix;
+pub mod profile;
 pub mod scorer;
 pub mod search;
 pub mod terminal;
+pub mod walker;
 
 use choice::Choice;
 use choices::Choices;
+use command::Placeholders;
 use config::Config;
-use search::Search;
+use events::Event;
+use keymap::{Action, Keymap};
+use search::{Operator, Search, ViMode};
 use std::error::Error;
 use std::io::{self, Read};
+use std::time::Instant;
 use terminal::Terminal;
-use termion::event::Key;
+use termion::event::{Key, MouseButton, MouseEvent};
 use rayon::prelude::*;
 
 pub struct App {}
@@ -33,27 +46,57 @@ impl App {
         let config = Config::new();
         let mut exit_code = 0;
 
+        // In vi mode Esc leaves insert mode rather than aborting, unless it's
+        // bound to something else.
+        let mut bindings = config.bindings.clone();
+        if config.vi {
+            bindings.insert(0, "esc:vi-normal-mode".to_string());
+        }
+        let vi_normal = Keymap::vi_normal();
+        let keymap = match Keymap::new(&bindings) {
+            Ok(keymap) => keymap,
+            Err(message) => clap::Error::with_description(
+                &message, clap::ErrorKind::InvalidValue
+            ).exit(),
+        };
+
         let mut terminal = Terminal::new()?;
         let stdin = io::stdin();
         let mut stdin_lock = stdin.lock();
 
         let mut buffer = String::new();
 
-       if termion::is_tty(&stdin_lock) {
-           clap::Error::with_description(
-               "No input given", clap::ErrorKind::MissingRequiredArgument
-           ).exit();
+        // Choices come from the source command if there is one, then from
+        // stdin, and without anything piped in from the current directory.
+        let walking = config.source.is_none() && termion::is_tty(&stdin_lock);
+        let reading = config.source.is_none() && !walking;
+        if config.benchmark && !reading {
+            clap::Error::with_description(
+                "No input given", clap::ErrorKind::MissingRequiredArgument
+            ).exit();
+        } else if walking && config.format.is_some() {
+            clap::Error::with_description(
+                "Records can only be parsed from stdin", clap::ErrorKind::MissingRequiredArgument
+            ).exit();
+        } else if reading {
+            stdin_lock.read_to_string(&mut buffer)?;
         }
+        let input = match input::parse(&buffer, &config) {
+            Ok(input) => input,
+            Err(message) => clap::Error::with_description(
+                &message, clap::ErrorKind::InvalidValue
+            ).exit(),
+        };
 
-        stdin_lock.read_to_string(&mut buffer)?;
-        let parsed_choices: Vec<Choice> =
-            buffer.
-            par_lines().
-            map(|choice| Choice::new(choice, &config)).
-            collect();
-
-        let mut search = Search::new(config.prompt);
-        let mut choices = Choices::new(config.lines, &parsed_choices, config.show_scores);
+        let mut search = Search::new(&config.prompt);
+        search.paint_prompt(&config.color);
+        if config.vi {
+            search.enable_vi();
+        }
+        let mut choices = Choices::new(&config, input.choices);
+        choices.add_header(input.header);
+        choices.set_skipped(input.skipped);
+        choices.set_loading(config.source.is_some() || walking);
 
         if config.benchmark {
             let query = config.query.unwrap().chars().collect::<Vec<char>>();
@@ -62,82 +105,334 @@ impl App {
             }
         } else {
             choices.initial_draw(&mut terminal);
-            match config.query {
+            match &config.query {
                 Some(query) => {
-                    terminal.print(&search.set_query(&query));
+                    terminal.print(&search.set_query(query));
                     terminal.print(&choices.filter(&search.query));
                 }
                 None => terminal.print(&search.draw())
             }
 
-            for c in terminal.keys()? {
-                match c.unwrap() {
-                    Key::Alt(c) => match c as u8 {
-                        b'b' => {
-                            terminal.print(&search.left_word());
-                            terminal.print(&choices.filter(&search.query));
-                        },
-                        b'f' => {
-                            terminal.print(&search.right_word());
-                            terminal.print(&choices.filter(&search.query));
-                        },
-                        127 => {
-                            terminal.print(&search.backspace_word());
-                            terminal.print(&choices.filter(&search.query));
-                        },
-                        100 => {
-                            terminal.print(&search.delete_word());
-                            terminal.print(&choices.filter(&search.query));
-                        },
-                        _ => {}
-                    },
-                    Key::Char('\n') => {
+            let (events, sender) = terminal.events()?;
+            // Clicks are hit-tested against where the prompt is on screen.
+            let mut prompt_row = None;
+            // Set while the rows are labelled, to whether jumping to one accepts it.
+            let mut jumping = None;
+            if config.mouse {
+                terminal.enable_mouse();
+                prompt_row = terminal.cursor_row()?;
+            }
+            // Each run of the source gets a new generation, output from older
+            // runs is dropped and the first batch of a new run replaces the choices.
+            let mut generation = 0;
+            let mut replacing = false;
+            let mut source = None;
+            if let Some(command) = &config.source {
+                let query: String = search.query.iter().collect();
+                let command = command::expand(command, &placeholders(&choices, &query));
+                source = Some(command::stream(&command, generation, sender.clone())?);
+            } else if walking {
+                walker::spawn(config.walker, sender.clone());
+            }
+
+            for event in events {
+                let key = match event {
+                    Event::Key(key) => key,
+                    Event::Mouse(MouseEvent::Press(MouseButton::Left, _, row)) => {
+                        // Resizing can reflow the screen and move the prompt.
+                        if terminal.resized() {
+                            prompt_row = terminal.cursor_row()?;
+                        }
+                        let offset = prompt_row.map(|prompt| row as isize - prompt as isize);
+                        if let Some((text, double)) = offset.and_then(|offset| choices.click(offset, Instant::now())) {
+                            terminal.print(&text);
+                            if double {
+                                choices.select(&mut terminal);
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+                    Event::Mouse(MouseEvent::Press(MouseButton::WheelUp, ..)) => {
+                        terminal.print(&choices.up());
+                        continue;
+                    }
+                    Event::Mouse(MouseEvent::Press(MouseButton::WheelDown, ..)) => {
+                        terminal.print(&choices.down());
+                        continue;
+                    }
+                    Event::Mouse(_) => continue,
+                    Event::Choices(id, lines) if id == generation => {
+                        let parsed: Vec<Choice> = lines.into_par_iter().map(|line| Choice::from_owned(line, &config)).collect();
+                        if replacing {
+                            replacing = false;
+                            terminal.print(&choices.replace(parsed));
+                        } else {
+                            terminal.print(&choices.extend(parsed));
+                        }
+                        continue;
+                    }
+                    Event::Tick(id) if id == generation => {
+                        terminal.print(&choices.tick());
+                        continue;
+                    }
+                    Event::Done(id) if id == generation => {
+                        choices.set_loading(false);
+                        if replacing {
+                            replacing = false;
+                            terminal.print(&choices.replace(vec![]));
+                        } else {
+                            terminal.print(&choices.draw());
+                        }
+                        continue;
+                    }
+                    Event::Choices(..) | Event::Tick(_) | Event::Done(_) => continue,
+                };
+
+                // The key after a jump picks a label, anything else cancels it.
+                if let Some(accept) = jumping.take() {
+                    let jumped = matches!(key, Key::Char(label) if choices.jump(label));
+                    terminal.print(&choices.stop_jump());
+                    if jumped && accept {
                         choices.select(&mut terminal);
                         break;
                     }
-                    Key::Char('\t') => {
-                        terminal.print(&search.set_query(choices.current_match().searchable));
-                        terminal.print(&choices.filter(&search.query));
-                    }
-                    Key::Char(c) => {
-                        terminal.print(&search.keypress(c));
-                        terminal.print(&choices.filter(&search.query));
+                    continue;
+                }
+
+                let normal = search.vi_mode() == Some(ViMode::Normal);
+                let action = if normal {
+                    vi_normal.action(&key).or_else(|| match key {
+                        Key::Char(c) if !c.is_control() => None,
+                        _ => keymap.action(&key),
+                    })
+                } else {
+                    keymap.action(&key)
+                };
+                // An operator only waits for the very next key.
+                if !matches!(
+                    action,
+                    Some(Action::ViDelete | Action::ViChange | Action::ViForwardWord | Action::ViBackwardWord | Action::ViEndOfWord)
+                ) {
+                    search.cancel_operator();
+                }
+
+                match action {
+                    Some(Action::Accept) => {
+                        choices.select(&mut terminal);
+                        break;
                     }
-                    Key::Ctrl('u') => terminal.print(&search.clear()),
-                    Key::Up => terminal.print(&choices.previous()),
-                    Key::Down => terminal.print(&choices.next()),
-                    Key::Esc | Key::Ctrl('c') => {
+                    Some(Action::Abort) => {
                         exit_code = 1;
                         terminal.print(&choices.cancel());
                         break;
                     }
-                    Key::Left => {
+                    Some(Action::ReplaceQuery) => {
+                        if let Some(choice) = choices.current() {
+                            terminal.print(&search.set_query(&choice.searchable));
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::Up) => terminal.print(&choices.up()),
+                    Some(Action::Down) => terminal.print(&choices.down()),
+                    Some(Action::PageUp) => terminal.print(&choices.page_up()),
+                    Some(Action::PageDown) => terminal.print(&choices.page_down()),
+                    Some(Action::First) => terminal.print(&choices.first()),
+                    Some(Action::Last) => terminal.print(&choices.last()),
+                    Some(Action::BackwardChar) => {
                         if let Some(text) = search.left() {
-                            terminal.print(text);
+                            terminal.print(&text);
                         }
                     }
-                    Key::Right => {
+                    Some(Action::ForwardChar) => {
                         if let Some(text) = search.right() {
-                            terminal.print(text);
+                            terminal.print(&text);
                         }
                     }
-                    Key::Backspace => {
+                    Some(Action::BackwardWord) => {
+                        terminal.print(&search.left_word());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::ForwardWord) => {
+                        terminal.print(&search.right_word());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::BackwardDeleteChar) => {
                         if let Some(text) = search.backspace() {
                             terminal.print(&text);
                             terminal.print(&choices.filter(&search.query));
                         }
                     }
-                    Key::Ctrl('d') => {
+                    Some(Action::BackwardKillWord) => {
+                        terminal.print(&search.backspace_word());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::DeleteChar) => {
                         if let Some(text) = search.delete() {
                             terminal.print(&text);
                             terminal.print(&choices.filter(&search.query));
                         }
                     }
-                    _ => {}
+                    Some(Action::KillWord) => {
+                        terminal.print(&search.delete_word());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::BeginningOfLine) => terminal.print(&search.beginning_of_line()),
+                    Some(Action::EndOfLine) => terminal.print(&search.end_of_line()),
+                    Some(Action::KillLine) => {
+                        terminal.print(&search.kill_line());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::UnixLineDiscard) => {
+                        terminal.print(&search.unix_line_discard());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::UnixWordRubout) => {
+                        terminal.print(&search.unix_word_rubout());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::Yank) => {
+                        if let Some(text) = search.yank() {
+                            terminal.print(&text);
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::YankPop) => {
+                        if let Some(text) = search.yank_pop() {
+                            terminal.print(&text);
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::Undo) => {
+                        if let Some(text) = search.undo() {
+                            terminal.print(&text);
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::Redo) => {
+                        if let Some(text) = search.redo() {
+                            terminal.print(&text);
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::ClearQuery) => {
+                        terminal.print(&search.clear());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::ToggleExact) => {
+                        choices.toggle_exact();
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::ToggleRegex) => {
+                        choices.toggle_regex();
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::Reload) => {
+                        if let Some(command) = &config.source {
+                            if let Some(child) = source.take() {
+                                command::stop(child);
+                            }
+                            generation += 1;
+                            replacing = true;
+                            choices.set_loading(true);
+
+                            let query: String = search.query.iter().collect();
+                            let command = command::expand(command, &placeholders(&choices, &query));
+                            source = Some(command::stream(&command, generation, sender.clone())?);
+                        }
+                    }
+                    Some(Action::ToggleMark) => terminal.print(&choices.toggle_mark()),
+                    Some(Action::Jump) => {
+                        jumping = Some(false);
+                        terminal.print(&choices.start_jump());
+                    }
+                    Some(Action::JumpAccept) => {
+                        jumping = Some(true);
+                        terminal.print(&choices.start_jump());
+                    }
+                    Some(Action::ViNormalMode) => terminal.print(&search.normal_mode()),
+                    Some(Action::ViInsert) => terminal.print(&search.insert()),
+                    Some(Action::ViAppend) => terminal.print(&search.append()),
+                    Some(Action::ViInsertAtStart) => terminal.print(&search.insert_at_start()),
+                    Some(Action::ViAppendAtEnd) => terminal.print(&search.append_at_end()),
+                    Some(Action::ViForwardWord) => {
+                        let (text, edited) = search.vi_forward_word();
+                        terminal.print(&text);
+                        if edited {
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::ViBackwardWord) => {
+                        let (text, edited) = search.vi_backward_word();
+                        terminal.print(&text);
+                        if edited {
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::ViEndOfWord) => {
+                        let (text, edited) = search.vi_end_of_word();
+                        terminal.print(&text);
+                        if edited {
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::ViDelete) => {
+                        if let Some(text) = search.operator(Operator::Delete) {
+                            terminal.print(&text);
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::ViChange) => {
+                        if let Some(text) = search.operator(Operator::Change) {
+                            terminal.print(&text);
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
+                    Some(Action::ViChangeToEnd) => {
+                        terminal.print(&search.change_to_end());
+                        terminal.print(&choices.filter(&search.query));
+                    }
+                    Some(Action::Execute(command)) => {
+                        let query: String = search.query.iter().collect();
+                        let command = command::expand(command, &placeholders(&choices, &query));
+
+                        terminal.print(&choices.cancel());
+                        terminal.suspend_raw_mode();
+                        command::run(&command, &terminal.output)?;
+                        terminal.resume_raw_mode()?;
+
+                        choices.redraw(&mut terminal);
+                        terminal.print(&search.draw());
+                        if config.mouse {
+                            prompt_row = terminal.cursor_row()?;
+                        }
+                    }
+                    Some(Action::ExecuteSilent(command)) => {
+                        let query: String = search.query.iter().collect();
+                        command::run_silent(&command::expand(command, &placeholders(&choices, &query)))?;
+                    }
+                    None => {
+                        if let (Key::Char(c), false) = (key, normal) {
+                            terminal.print(&search.keypress(c));
+                            terminal.print(&choices.filter(&search.query));
+                        }
+                    }
                 }
             }
+
+            if let Some(child) = source {
+                command::stop(child);
+            }
         }
 
         Ok(exit_code)
     }
 }
+
+fn placeholders<'p>(choices: &'p Choices, query: &'p str) -> Placeholders<'p> {
+    Placeholders {
+        current: choices.current().map(|choice| choice.returnable.as_ref()),
+        query,
+        marked: choices.marked(),
+    }
+}
@@ -1,19 +1,122 @@
+use crate::ansi;
 use crate::choice::Choice;
-use crate::color;
+use crate::color::Theme;
 use crate::cursor;
+use crate::profile::ScoringProfile;
 use crate::scorer::{Score, MIN};
+use memchr::{memchr, memchr2};
+use regex::{Regex, RegexBuilder};
 use std::cmp::Ordering;
-use std::{fmt, cmp};
+use std::ops::Range;
 
 use terminal_size::{Width, Height, terminal_size};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Drawn in place of the characters that don't fit.
+const ELLIPSIS: char = '…';
+// Scores are drawn as `( 0.99) ` before the choice.
+const SCORE_WIDTH: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Fuzzy,
+    Exact,
+    Regex,
+}
+
+impl Mode {
+    pub fn toggle_exact(self) -> Mode {
+        match self {
+            Mode::Exact => Mode::Fuzzy,
+            _ => Mode::Exact,
+        }
+    }
+
+    pub fn toggle_regex(self) -> Mode {
+        match self {
+            Mode::Regex => Mode::Fuzzy,
+            _ => Mode::Regex,
+        }
+    }
+}
+
+pub enum Pattern<'q> {
+    Fuzzy(&'q [char]),
+    Typos(&'q [char], usize),
+    Exact(&'q [char]),
+    Regex(Regex),
+}
 
-pub struct Match<'a> {
-    pub choice: &'a Choice<'a>,
+impl<'q> Pattern<'q> {
+    pub fn new(query: &'q [char], mode: Mode, typos: usize) -> Result<Self, String> {
+        match mode {
+            Mode::Fuzzy if typos > 0 => Ok(Pattern::Typos(query, typos)),
+            Mode::Fuzzy => Ok(Pattern::Fuzzy(query)),
+            Mode::Exact => Ok(Pattern::Exact(query)),
+            // An empty regex matches everything, treat it like an empty query
+            Mode::Regex if query.is_empty() => Ok(Pattern::Fuzzy(query)),
+            Mode::Regex => {
+                let source: String = query.iter().collect();
+
+                RegexBuilder::new(&source)
+                    .case_insensitive(!has_uppercase_literal(query))
+                    .build()
+                    .map(Pattern::Regex)
+                    .map_err(|error| match error {
+                        regex::Error::Syntax(message) => {
+                            message.lines().last().unwrap_or_default().to_string()
+                        }
+                        _ => error.to_string(),
+                    })
+            }
+        }
+    }
+}
+
+// Whether the regex has an uppercase letter of its own, leaving out escapes
+// like `\S` and `\W` and classes like `\p{Lu}` whose letters name what they
+// match rather than matching themselves.
+fn has_uppercase_literal(query: &[char]) -> bool {
+    let mut chars = query.iter().peekable();
+
+    while let Some(&c) = chars.next() {
+        if c == '\\' {
+            let escape = chars.next();
+            if matches!(escape, Some('p' | 'P')) && chars.peek() == Some(&&'{') {
+                chars.by_ref().find(|&&c| c == '}');
+            }
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+
+    false
+}
+
+// How a row is drawn. Choices keeps the options that come from the config and
+// fills in the rest for each row.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DrawOptions {
+    pub selected: bool,
+    pub show_scores: bool,
+    pub keep_right: bool,
+    // Columns drawn in front of the row, such as a jump label.
+    pub indent: usize,
+}
+
+// A choice that matched the query, referred to by its index in the choices.
+pub struct Match {
+    pub index: usize,
     scorer: Score,
 }
 
-impl<'a> Match<'a> {
+impl Match {
     pub fn is_match(query: &[char], choice: &Choice) -> bool {
+        if choice.ascii {
+            return Self::is_ascii_match(query, choice);
+        }
+
         // Saving the enumerator outside the iterator will ensure chars are in
         // order and will make it so we only ever go through the choice once.
         let mut choice_chars = choice.searchable.chars();
@@ -25,83 +128,229 @@ impl<'a> Match<'a> {
         })
     }
 
-    pub fn new(query: &[char], choice: &'a Choice) -> Option<Self> {
-        if Self::is_match(query, choice) {
-            Some(Self {
-                choice,
-                scorer: Score::new(query, choice),
-            })
-        } else {
-            None
-        }
+    // Same rules as is_match but jumps straight to the next occurrence of each
+    // query byte instead of walking the choice a char at a time.
+    fn is_ascii_match(query: &[char], choice: &Choice) -> bool {
+        let haystack = choice.searchable.as_bytes();
+        let mut offset = 0;
+
+        query.iter().all(|nchar| {
+            if !nchar.is_ascii() {
+                return false;
+            }
+
+            let lower = *nchar as u8;
+            let upper = lower.to_ascii_uppercase();
+            let found = if lower == upper {
+                memchr(lower, &haystack[offset..])
+            } else {
+                memchr2(lower, upper, &haystack[offset..])
+            };
+
+            match found {
+                Some(index) => {
+                    offset += index + 1;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    pub fn new(pattern: &Pattern, index: usize, choice: &Choice, profile: &ScoringProfile) -> Option<Self> {
+        let scorer = match pattern {
+            Pattern::Fuzzy(query) if Self::is_match(query, choice) => Some(Score::new(query, choice, profile)),
+            Pattern::Fuzzy(_) => None,
+            Pattern::Typos(query, _) if Self::is_match(query, choice) => Some(Score::new(query, choice, profile)),
+            Pattern::Typos(query, typos) => Score::typos(query, choice, *typos, profile),
+            Pattern::Exact(query) => Score::exact(query, choice, profile),
+            Pattern::Regex(regex) => Score::regex(regex, choice),
+        };
+
+        scorer.map(|scorer| Self { index, scorer })
+    }
+
+    // Draws the row for a choice, fitting it into the terminal's width less
+    // the indent drawn in front of it.
+    pub fn draw(&self, choice: &Choice, options: &DrawOptions, theme: &Theme) -> String {
+        let width = terminal_size().map(|(Width(w), Height(_h))| usize::from(w).saturating_sub(options.indent));
+
+        self.draw_within(choice, options, theme, width)
     }
 
-    pub fn draw(&self, selected: bool, show_scores: bool) -> String {
-        let mut drawn = self.draw_highlights();
+    fn draw_within(&self, choice: &Choice, options: &DrawOptions, theme: &Theme, mut width: Option<usize>) -> String {
+        let mut score = None;
 
-        if show_scores {
+        if options.show_scores {
             let current_score = self.scorer.score;
-            if current_score != MIN {
-                drawn = format!("({:5.2}) {}", current_score, drawn);
+            score = Some(if current_score != MIN && choice.approximate {
+                format!("(~{:4.2})", current_score)
+            } else if current_score != MIN {
+                format!("({:5.2})", current_score)
             } else {
-                drawn = format!("(     ) {}", drawn);
-            }
+                "(     )".to_string()
+            });
+            width = width.map(|width| width.saturating_sub(SCORE_WIDTH));
+        }
+
+        let mut drawn = self.draw_highlights(choice, options.selected, theme, width, options.keep_right);
+        if let Some(score) = score {
+            drawn = format!("{} {}", theme.score(&score), drawn);
         }
 
-        if selected {
-            cursor::nowrap(&color::inverse(&drawn))
+        if options.selected {
+            cursor::nowrap(&theme.selected(&drawn))
         } else {
             cursor::nowrap(&drawn)
         }
     }
 
-    fn draw_highlights(&self) -> String {
-        let content = self.choice.searchable;
+    // Draws the displayed text with its matched characters highlighted, cut
+    // down to `width` columns around the highlights with ellipses where it
+    // doesn't fit. The text is cut between grapheme clusters, so a character
+    // and its combining marks are kept or dropped together.
+    fn draw_highlights(&self, choice: &Choice, selected: bool, theme: &Theme, width: Option<usize>, keep_right: bool) -> String {
+        let positions = choice.display_positions(&self.scorer.positions);
+        let mut graphemes = vec![];
+        let mut chars = 0;
+        for grapheme in choice.display.graphemes(true) {
+            let count = grapheme.chars().count();
+            graphemes.push((chars..chars + count, grapheme));
+            chars += count;
+        }
+        let shown = match width {
+            Some(width) => {
+                let widths: Vec<usize> = graphemes.iter().map(|(_, grapheme)| grapheme.width()).collect();
+                let highlighted: Vec<usize> = graphemes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (chars, _))| chars.clone().any(|i| positions.contains(&i)))
+                    .map(|(i, _)| i)
+                    .collect();
+                let highlights = highlighted.first().zip(highlighted.last()).map(|(&first, &last)| (first, last));
+
+                window(&widths, width, highlights, keep_right)
+            }
+            None => 0..graphemes.len(),
+        };
 
-        let characters;
-        if let Some((Width(w), Height(_h))) = terminal_size() {
-            let length = cmp::min(content.chars().count(), usize::from(w));
+        // Styles kept from the input are re-emitted where they were, and the
+        // ones in effect are restored after each highlight.
+        let mut styles = if theme.enabled { choice.display_styles() } else { vec![] }.into_iter().peekable();
+        let mut active = String::new();
+        let mut drawn = String::new();
 
-            characters = content[0..length].chars();
-        } else {
-            characters = content.chars();
+        if shown.start > 0 {
+            drawn.push(ELLIPSIS);
         }
+        for (chars, grapheme) in &graphemes[shown.clone()] {
+            while let Some((_, sequence)) = styles.next_if(|&(at, _)| at < chars.end) {
+                if ansi::resets(sequence) {
+                    active.clear();
+                }
+                active.push_str(sequence);
+                drawn.push_str(sequence);
+                if selected {
+                    drawn.push_str(&theme.selection());
+                }
+            }
 
-        characters
-            .enumerate()
-            .map(|(i, cchar)| {
-                if self.scorer.positions.contains(&i) {
-                    color::highlight(cchar)
-                } else {
-                    cchar.to_string()
+            if chars.clone().any(|i| positions.contains(&i)) {
+                drawn.push_str(&theme.matched(grapheme, selected));
+                drawn.push_str(&active);
+                // Restoring a reset would clear the selection for the rest of the row.
+                if selected && !active.is_empty() && ansi::resets(&active) {
+                    drawn.push_str(&theme.selection());
                 }
-            })
-            .collect()
+            } else {
+                drawn.push_str(grapheme);
+            }
+        }
+        if shown.end < graphemes.len() {
+            drawn.push(ELLIPSIS);
+        }
+        if !active.is_empty() {
+            drawn.push_str("\x1B[0m");
+        }
+
+        drawn
+    }
+}
+
+// Which graphemes to draw so they fit in `width` columns, leaving room for an
+// ellipsis on either side where some are left out. The start of the text is
+// shown, or its end when keeping right, as long as that shows every highlight.
+// Otherwise the other end is tried, since the end of a path is usually its
+// most telling part, and failing that the text is shown up to just past the
+// last highlight.
+fn window(widths: &[usize], width: usize, highlights: Option<(usize, usize)>, keep_right: bool) -> Range<usize> {
+    if widths.iter().sum::<usize>() <= width {
+        return 0..widths.len();
+    }
+
+    let head = 0..fill_from(widths, 0, width);
+    let tail = fill_to(widths, widths.len(), width)..widths.len();
+    let shows_highlights = |range: &Range<usize>| {
+        highlights.is_none_or(|(first, last)| range.start <= first && last < range.end)
+    };
+    let (preferred, other) = if keep_right { (tail, head) } else { (head, tail) };
+
+    match highlights {
+        _ if shows_highlights(&preferred) => preferred,
+        _ if shows_highlights(&other) => other,
+        Some((_, last)) => {
+            let end = (last + 2).min(widths.len());
+            fill_to(widths, end, width)..end
+        }
+        None => preferred,
+    }
+}
+
+// Where a window starting at `start` has to end to fit in `width` columns.
+fn fill_from(widths: &[usize], start: usize, width: usize) -> usize {
+    let width = width.saturating_sub(usize::from(start > 0));
+    let fits = |width: usize| {
+        let mut used = 0;
+        start + widths[start..].iter().take_while(|&&w| { used += w; used <= width }).count()
+    };
+
+    match fits(width) {
+        end if end == widths.len() => end,
+        _ => fits(width.saturating_sub(1)),
     }
 }
 
-impl<'a> fmt::Display for Match<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.choice.searchable)
+// Where a window ending at `end` has to start to fit in `width` columns.
+fn fill_to(widths: &[usize], end: usize, width: usize) -> usize {
+    let width = width.saturating_sub(usize::from(end < widths.len()));
+    let fits = |width: usize| {
+        let mut used = 0;
+        end - widths[..end].iter().rev().take_while(|&&w| { used += w; used <= width }).count()
+    };
+
+    match fits(width) {
+        0 => 0,
+        _ => fits(width.saturating_sub(1)),
     }
 }
 
-impl<'a> Ord for Match<'a> {
+impl Ord for Match {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        self.scorer.typos.cmp(&other.scorer.typos)
+            .then_with(|| other.scorer.score.partial_cmp(&self.scorer.score).unwrap())
     }
 }
 
-impl<'a> PartialOrd for Match<'a> {
+impl PartialOrd for Match {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.scorer.score.partial_cmp(&self.scorer.score)
+        Some(self.cmp(other))
     }
 }
 
-impl<'a> Eq for Match<'a> {}
-impl<'a> PartialEq for Match<'a> {
+impl Eq for Match {}
+impl PartialEq for Match {
     fn eq(&self, other: &Self) -> bool {
-        other.scorer.score == self.scorer.score
+        other.scorer.typos == self.scorer.typos && other.scorer.score == self.scorer.score
     }
 }
 
@@ -132,6 +381,80 @@ mod tests {
         // UTF-8 case testing
         assert!(new_match("a", &make_choice("A")).is_some());
         assert!(new_match("A", &make_choice("a")).is_none());
+
+        assert!(new_match("💣", &make_choice("test")).is_none());
+        assert!(new_match("t💣", &make_choice("t💣e")).is_some());
+        assert!(new_match("tt", &make_choice("t")).is_none());
+        assert!(new_match("a/b", &make_choice("a/b/c")).is_some());
+        assert!(new_match("b/a", &make_choice("a/b/c")).is_none());
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let choice = make_choice("app/models/order.rb");
+
+        assert!(new_match_with("ord", &choice, Mode::Exact).is_some());
+        assert!(new_match_with("amo", &choice, Mode::Exact).is_none());
+        assert!(new_match_with("amo", &choice, Mode::Fuzzy).is_some());
+    }
+
+    #[test]
+    fn test_regex_match() {
+        let choice = make_choice("app/models/Order.rb");
+
+        assert!(new_match_with("mod.*rb$", &choice, Mode::Regex).is_some());
+        assert!(new_match_with("order", &choice, Mode::Regex).is_some());
+        assert!(new_match_with("ORDER", &choice, Mode::Regex).is_none());
+        assert!(new_match_with("\\Sorder", &choice, Mode::Regex).is_some());
+        assert!(new_match_with("\\p{Lu}rder", &choice, Mode::Regex).is_some());
+        assert!(new_match_with("\\SOrder", &choice, Mode::Regex).is_some());
+        assert!(new_match_with("\\Sorder\\W", &choice, Mode::Regex).is_some());
+        assert!(new_match_with("^models", &choice, Mode::Regex).is_none());
+        assert!(new_match_with("", &choice, Mode::Regex).is_some());
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let query = ['(', 'a'];
+
+        assert_eq!(Some("error: unclosed group".to_string()), Pattern::new(&query, Mode::Regex, 0).err());
+    }
+
+    #[test]
+    fn test_typos_match() {
+        let choice = make_choice("app/controllers");
+
+        assert!(new_match("contorller", &choice).is_none());
+        assert!(new_match_with_typos("contorller", &choice, 1).is_some());
+        assert!(new_match_with_typos("cnotorller", &choice, 1).is_none());
+    }
+
+    #[test]
+    fn test_typos_rank_after_exact() {
+        let exact = make_choice("app/contorller_thing/with/a/long/path/x");
+        let typo = make_choice("contrller");
+        let exact_match = new_match_with_typos("contorller", &exact, 1).unwrap();
+        let typo_match = new_match_with_typos("contorller", &typo, 1).unwrap();
+
+        assert!(exact_match.scorer.score < typo_match.scorer.score);
+        assert!(exact_match < typo_match);
+    }
+
+    #[test]
+    fn test_mode_toggles() {
+        assert_eq!(Mode::Exact, Mode::Fuzzy.toggle_exact());
+        assert_eq!(Mode::Fuzzy, Mode::Exact.toggle_exact());
+        assert_eq!(Mode::Exact, Mode::Regex.toggle_exact());
+        assert_eq!(Mode::Regex, Mode::Exact.toggle_regex());
+        assert_eq!(Mode::Fuzzy, Mode::Regex.toggle_regex());
+    }
+
+    #[test]
+    fn test_drawing_regex_highlights() {
+        let choice = make_choice("foo");
+        let matcher = new_match_with("o+", &choice, Mode::Regex).unwrap();
+
+        assert_eq!("\x1B[?7lf\x1B[33mo\x1B[39m\x1B[33mo\x1B[39m\x1B[?7h", matcher.draw(&choice, &DrawOptions::default(), &Theme::default()));
     }
 
     #[test]
@@ -139,7 +462,7 @@ mod tests {
         let choice = make_choice("foo");
         let matcher = new_match("", &choice).unwrap();
 
-        assert_eq!("\x1B[?7lfoo\x1B[?7h", matcher.draw(false, false));
+        assert_eq!("\x1B[?7lfoo\x1B[?7h", matcher.draw(&choice, &DrawOptions::default(), &Theme::default()));
     }
 
     #[test]
@@ -154,7 +477,7 @@ mod tests {
         let choice = make_choice("foo");
         let matcher = new_match("", &choice).unwrap();
 
-        assert_eq!("\x1B[?7l\x1B[7mfoo\x1B[27m\x1B[?7h", matcher.draw(true, false));
+        assert_eq!("\x1B[?7l\x1B[7mfoo\x1B[27m\x1B[?7h", matcher.draw(&choice, &DrawOptions { selected: true, ..DrawOptions::default() }, &Theme::default()));
     }
 
     #[test]
@@ -162,7 +485,7 @@ mod tests {
         let choice = make_choice("foo");
         let matcher = new_match("f", &choice).unwrap();
 
-        assert_eq!("\x1B[?7l\x1B[33mf\x1B[39moo\x1B[?7h", matcher.draw(false, false));
+        assert_eq!("\x1B[?7l\x1B[33mf\x1B[39moo\x1B[?7h", matcher.draw(&choice, &DrawOptions::default(), &Theme::default()));
     }
 
     #[test]
@@ -170,7 +493,7 @@ mod tests {
         let choice = make_choice("foo");
         let matcher = new_match("f", &choice).unwrap();
 
-        assert_eq!("\x1B[?7l\x1B[7m\x1B[33mf\x1B[39moo\x1B[27m\x1B[?7h", matcher.draw(true, false));
+        assert_eq!("\x1B[?7l\x1B[7m\x1B[33mf\x1B[39moo\x1B[27m\x1B[?7h", matcher.draw(&choice, &DrawOptions { selected: true, ..DrawOptions::default() }, &Theme::default()));
     }
 
     #[test]
@@ -178,7 +501,7 @@ mod tests {
         let choice = make_choice("foo");
         let matcher = new_match("", &choice).unwrap();
 
-        assert_eq!("\x1B[?7l(     ) foo\x1B[?7h", matcher.draw(false, true))
+        assert_eq!("\x1B[?7l(     ) foo\x1B[?7h", matcher.draw(&choice, &DrawOptions { show_scores: true, ..DrawOptions::default() }, &Theme::default()))
     }
 
     #[test]
@@ -186,7 +509,15 @@ mod tests {
         let choice = make_choice("foo");
         let matcher = new_match("f", &choice).unwrap();
 
-        assert_eq!("\x1B[?7l( 0.99) \u{1b}[33mf\u{1b}[39moo\x1B[?7h", matcher.draw(false, true))
+        assert_eq!("\x1B[?7l( 0.99) \u{1b}[33mf\u{1b}[39moo\x1B[?7h", matcher.draw(&choice, &DrawOptions { show_scores: true, ..DrawOptions::default() }, &Theme::default()))
+    }
+
+    #[test]
+    fn drawing_with_show_scores_approximate_test() {
+        let choice = Choice::new("foo", &Config { max_length: 2, ..Config::default() });
+        let matcher = new_match("f", &choice).unwrap();
+
+        assert_eq!("\x1B[?7l(~0.99) \u{1b}[33mf\u{1b}[39moo\x1B[?7h", matcher.draw(&choice, &DrawOptions { show_scores: true, ..DrawOptions::default() }, &Theme::default()))
     }
 
     #[bench]
@@ -205,25 +536,117 @@ mod tests {
         b.iter(|| Match::is_match(&query, &choice))
     }
 
+    #[bench]
+    fn bench_matching_long(b: &mut test::Bencher) {
+        let choice = make_choice("app/javascript/controllers/application/order_items_controller.js");
+        let query = ['o', 'r', 'd', 'e', 'r', 'c', 't', 'l'];
+
+        b.iter(|| Match::is_match(&query, &choice))
+    }
+
     #[bench]
     fn bench_drawing(b: &mut test::Bencher) {
         let choice = make_choice("CODE_OF_CONDUCT.md");
         let match_ins = new_match("code", &choice).unwrap();
 
-        b.iter(|| match_ins.draw(false, false))
+        b.iter(|| match_ins.draw(&choice, &DrawOptions::default(), &Theme::default()))
     }
 
-    fn new_match<'a>(query: &str, choice: &'a Choice) -> Option<Match<'a>> {
-        Match::new(&query.chars().collect::<Vec<char>>(), choice)
+    #[test]
+    fn test_drawing_ansi_styles() {
+        let config = Config { ansi: true, ..Config::default() };
+        let choice = Choice::new("\x1B[32mfoo\x1B[0m bar", &config);
+        let matcher = new_match("o", &choice).unwrap();
+
+        assert_eq!(
+            "\x1B[?7l\x1B[32mfo\x1B[33mo\x1B[39m\x1B[32m\x1B[0m bar\x1B[0m\x1B[?7h",
+            matcher.draw(&choice, &DrawOptions::default(), &Theme::default())
+        );
+        assert_eq!(
+            "\x1B[?7l\x1B[7m\x1B[32m\x1B[7mfo\x1B[33mo\x1B[39m\x1B[32m\x1B[0m\x1B[7m bar\x1B[0m\x1B[27m\x1B[?7h",
+            matcher.draw(&choice, &DrawOptions { selected: true, ..DrawOptions::default() }, &Theme::default())
+        );
     }
 
-    fn make_choice(choice: &str) -> Choice {
-        let config = Config {
-            lines: 10, prompt: ">".to_string(), show_scores: false,
-            query: None, delimiter: None, field: None, output: None,
-            benchmark: false,
-        };
+    #[test]
+    fn test_drawing_ansi_styles_selected_after_reset() {
+        let config = Config { ansi: true, ..Config::default() };
+        let choice = Choice::new("\x1B[32mfoo\x1B[0m bar", &config);
+        let matcher = new_match("b", &choice).unwrap();
+
+        assert_eq!(
+            "\x1B[32m\x1B[7mfoo\x1B[0m\x1B[7m \x1B[33mb\x1B[39m\x1B[0m\x1B[7mar\x1B[0m",
+            matcher.draw_highlights(&choice, true, &Theme::default(), None, false)
+        );
+    }
+
+    #[test]
+    fn test_draw_within_with_scores() {
+        let choice = make_choice("foobarbaz");
+        let matcher = new_match("f", &choice).unwrap();
+
+        assert_eq!(
+            "\x1B[?7l( 0.96) \x1B[33mf\x1B[39mo…\x1B[?7h",
+            matcher.draw_within(&choice, &DrawOptions { show_scores: true, ..DrawOptions::default() }, &Theme::default(), Some(11))
+        );
+    }
+
+    #[test]
+    fn test_draw_truncated_by_width() {
+        let choice = make_choice("t💣e💣s💣t");
+        let matcher = new_match("e", &choice).unwrap();
+
+        assert_eq!("t💣\x1B[33me\x1B[39m…", matcher.draw_highlights(&choice, false, &Theme::default(), Some(6), false));
+        assert_eq!("t💣\x1B[33me\x1B[39m💣s💣t", matcher.draw_highlights(&choice, false, &Theme::default(), Some(10), false));
+    }
+
+    #[test]
+    fn test_draw_truncated_by_grapheme() {
+        let choice = make_choice("e\u{301}xyz");
+        let matcher = new_match("x", &choice).unwrap();
+
+        assert_eq!("e\u{301}\x1B[33mx\x1B[39m…", matcher.draw_highlights(&choice, false, &Theme::default(), Some(3), false));
+    }
+
+    #[test]
+    fn test_draw_scrolled_to_highlights() {
+        let choice = make_choice("app/models/concerns/order.rb");
+        let matcher = new_match("ord", &choice).unwrap();
+
+        assert_eq!("…ns/\x1B[33mo\x1B[39m\x1B[33mr\x1B[39m\x1B[33md\x1B[39mer.rb", matcher.draw_highlights(&choice, false, &Theme::default(), Some(12), false));
+    }
+
+    #[test]
+    fn test_window() {
+        let widths = [1; 10];
+
+        assert_eq!(0..5, window(&widths, 6, Some((1, 2)), false));
+        assert_eq!(5..9, window(&widths, 6, Some((1, 7)), false));
+        assert_eq!(5..10, window(&widths, 6, None, true));
+        assert_eq!(0..5, window(&widths, 6, Some((1, 2)), true));
+        assert_eq!(4..8, window(&widths, 6, Some((4, 6)), true));
+        assert_eq!(0..10, window(&widths, 10, Some((9, 9)), false));
+    }
+
+    fn new_match(query: &str, choice: &Choice) -> Option<Match> {
+        new_match_with(query, choice, Mode::Fuzzy)
+    }
+
+    fn new_match_with(query: &str, choice: &Choice, mode: Mode) -> Option<Match> {
+        let query = query.chars().collect::<Vec<char>>();
+        let pattern = Pattern::new(&query, mode, 0).unwrap();
+
+        Match::new(&pattern, 0, choice, &ScoringProfile::PATH)
+    }
+
+    fn new_match_with_typos(query: &str, choice: &Choice, typos: usize) -> Option<Match> {
+        let query = query.chars().collect::<Vec<char>>();
+        let pattern = Pattern::new(&query, Mode::Fuzzy, typos).unwrap();
+
+        Match::new(&pattern, 0, choice, &ScoringProfile::PATH)
+    }
 
-        Choice::new(choice, &config)
+    fn make_choice(choice: &str) -> Choice<'_> {
+        Choice::new(choice, &Config::default())
     }
 }
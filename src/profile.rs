@@ -0,0 +1,107 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScoringProfile {
+    pub start: f32,
+    pub slash: f32,
+    pub word: f32,
+    pub dot: f32,
+    pub capital: f32,
+    pub colon: f32,
+    pub filename: f32,
+    pub gap_leading: f32,
+    pub gap_inner: f32,
+    pub gap_trailing: f32,
+    pub match_consecutive: f32,
+}
+
+impl ScoringProfile {
+    pub const NAMES: [&'static str; 3] = ["path", "plain", "code"];
+
+    // Tuned for file paths, favoring the start of each section and the file name.
+    pub const PATH: ScoringProfile = ScoringProfile {
+        start: 0.9,
+        slash: 0.9,
+        word: 0.87,
+        dot: 0.6,
+        capital: 0.68,
+        colon: 0.0,
+        filename: 0.1,
+        gap_leading: -0.005,
+        gap_inner: -0.01,
+        gap_trailing: -0.005,
+        match_consecutive: 1.0,
+    };
+
+    // For prose and history lines, with no bonus after a slash or in the file name.
+    pub const PLAIN: ScoringProfile = ScoringProfile {
+        slash: 0.0,
+        filename: 0.0,
+        ..Self::PATH
+    };
+
+    // For symbols, where camelCase humps separate words and `::` as strongly as a slash.
+    pub const CODE: ScoringProfile = ScoringProfile {
+        capital: 0.87,
+        colon: 0.9,
+        filename: 0.0,
+        ..Self::PATH
+    };
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "path" => Some(Self::PATH),
+            "plain" => Some(Self::PLAIN),
+            "code" => Some(Self::CODE),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: f32) -> Result<(), String> {
+        let weight = match key {
+            "bonus.start" => &mut self.start,
+            "bonus.slash" => &mut self.slash,
+            "bonus.word" => &mut self.word,
+            "bonus.dot" => &mut self.dot,
+            "bonus.capital" => &mut self.capital,
+            "bonus.colon" => &mut self.colon,
+            "bonus.filename" => &mut self.filename,
+            "gap.leading" => &mut self.gap_leading,
+            "gap.inner" => &mut self.gap_inner,
+            "gap.trailing" => &mut self.gap_trailing,
+            "match.consecutive" => &mut self.match_consecutive,
+            _ => return Err(format!("Unknown scoring weight '{}'", key)),
+        };
+        *weight = value;
+
+        Ok(())
+    }
+}
+
+impl Default for ScoringProfile {
+    fn default() -> Self {
+        Self::PATH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Some(ScoringProfile::PATH), ScoringProfile::from_name("path"));
+        assert_eq!(Some(ScoringProfile::PLAIN), ScoringProfile::from_name("plain"));
+        assert_eq!(Some(ScoringProfile::CODE), ScoringProfile::from_name("code"));
+        assert_eq!(None, ScoringProfile::from_name("prose"));
+    }
+
+    #[test]
+    fn test_set() {
+        let mut profile = ScoringProfile::PATH;
+
+        assert!(profile.set("bonus.slash", 2.0).is_ok());
+        assert!(profile.set("gap.inner", -0.5).is_ok());
+        assert!(profile.set("bonus.tilde", 1.0).is_err());
+        assert_eq!(2.0, profile.slash);
+        assert_eq!(-0.5, profile.gap_inner);
+    }
+}
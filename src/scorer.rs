@@ -1,21 +1,19 @@
-use std::f32::{INFINITY, NEG_INFINITY};
 use crate::choice::Choice;
 use crate::matrix::Matrix;
+use crate::profile::ScoringProfile;
 use float_cmp::approx_eq;
+use regex::Regex;
 
-const MAX: f32 = INFINITY;
-pub const MIN: f32 = NEG_INFINITY;
-const GAP_TRAILING: f32 = -0.005;
-const GAP_INNER: f32 = -0.01;
-const GAP_LEADING:       f32 = -0.005;
-const MATCH_CONSECUTIVE: f32 = 1.0;
+const MAX: f32 = f32::INFINITY;
+pub const MIN: f32 = f32::NEG_INFINITY;
 
 pub struct Score {
     pub score: f32,
     pub positions: Vec<usize>,
+    pub typos: usize,
 }
 
-fn positions(choice_length: usize, query_length: usize, main: Matrix, diagonal: Matrix) -> Vec<usize> {
+fn positions(choice_length: usize, query_length: usize, main: Matrix, diagonal: Matrix, profile: &ScoringProfile) -> Vec<usize> {
     let mut positions = vec![0_usize; query_length];
 
     let mut match_required = false;
@@ -28,9 +26,9 @@ fn positions(choice_length: usize, query_length: usize, main: Matrix, diagonal:
 
             if d != MIN && (match_required || approx_eq!(f32, d, m)) {
                 // If this score was determined using
-                // MATCH_CONSECUTIVE, the previous character MUST be a match
+                // match_consecutive, the previous character MUST be a match
                 match_required = query_index > 0 && choice_index > 0 &&
-                    approx_eq!(f32, m, diagonal[(query_index - 1, choice_index - 1)] + MATCH_CONSECUTIVE);
+                    approx_eq!(f32, m, diagonal[(query_index - 1, choice_index - 1)] + profile.match_consecutive);
                 positions[query_index] = choice_index;
                 choice_index -= 1;
                 break;
@@ -42,7 +40,7 @@ fn positions(choice_length: usize, query_length: usize, main: Matrix, diagonal:
     positions
 }
 
-fn compute(query: &[char], choice: &Choice, query_length: usize, choice_length: usize) -> (Matrix, Matrix){
+fn compute(query: &[char], choice: &Choice, query_length: usize, choice_length: usize, profile: &ScoringProfile) -> (Matrix, Matrix){
     let lower_query: Vec<char> = query.iter().map(|qchar| qchar.to_ascii_lowercase()).collect();
     let mut diagonal = Matrix::new(query_length, choice_length);
     let mut main = Matrix::new(query_length, choice_length);
@@ -50,9 +48,9 @@ fn compute(query: &[char], choice: &Choice, query_length: usize, choice_length:
     lower_query.iter().enumerate().for_each(|(i, qchar)| {
         let mut prev_score = MIN;
         let gap_score = if i == query_length - 1 {
-            GAP_TRAILING
+            profile.gap_trailing
         } else {
-            GAP_INNER
+            profile.gap_inner
         };
 
         choice.lower_searchable.iter().enumerate().for_each(|(j, cchar)| {
@@ -60,12 +58,12 @@ fn compute(query: &[char], choice: &Choice, query_length: usize, choice_length:
                 let bonus_score = choice.bonus[j];
 
                 let current_score = if i == 0 {
-                    (j as f32 * GAP_LEADING) + bonus_score
+                    (j as f32 * profile.gap_leading) + bonus_score
                 } else if j > 0 {
                     let m_score = main[(i - 1, j - 1)];
                     let d_score = diagonal[(i - 1, j - 1)];
 
-                    (m_score + bonus_score).max(d_score + MATCH_CONSECUTIVE)
+                    (m_score + bonus_score).max(d_score + profile.match_consecutive)
                 } else {
                     MIN
                 };
@@ -86,33 +84,222 @@ fn compute(query: &[char], choice: &Choice, query_length: usize, choice_length:
     (main, diagonal)
 }
 
+// Finds the first complete match walking forwards, then walks back from where
+// it ended to tighten it. Linear in the length of the choice, so it is used in
+// place of the matrices for choices over the max length.
+fn greedy(query: &[char], choice: &Choice, query_length: usize, choice_length: usize, profile: &ScoringProfile) -> Score {
+    let lower_query: Vec<char> = query.iter().map(|qchar| qchar.to_ascii_lowercase()).collect();
+
+    let mut query_index = 0;
+    let mut end = 0;
+    for (j, cchar) in choice.lower_searchable.iter().enumerate() {
+        if *cchar == lower_query[query_index] {
+            query_index += 1;
+
+            if query_index == query_length {
+                end = j;
+                break;
+            }
+        }
+    }
+
+    let mut positions = vec![0_usize; query_length];
+    for j in (0..=end).rev() {
+        if choice.lower_searchable[j] == lower_query[query_index - 1] {
+            query_index -= 1;
+            positions[query_index] = j;
+
+            if query_index == 0 {
+                break;
+            }
+        }
+    }
+
+    Score { score: score_positions(&positions, choice, choice_length, profile), positions, typos: 0 }
+}
+
+// Scores a known set of positions the same way the matrices would.
+fn score_positions(positions: &[usize], choice: &Choice, choice_length: usize, profile: &ScoringProfile) -> f32 {
+    let mut score = positions[0] as f32 * profile.gap_leading + choice.bonus[positions[0]];
+    positions.windows(2).for_each(|pair| {
+        let (previous, current) = (pair[0], pair[1]);
+
+        if current == previous + 1 {
+            score += profile.match_consecutive.max(choice.bonus[current]);
+        } else {
+            score += (current - previous - 1) as f32 * profile.gap_inner + choice.bonus[current];
+        }
+    });
+
+    score + (choice_length - 1 - positions[positions.len() - 1]) as f32 * profile.gap_trailing
+}
+
+// The choice's chars as given, only needed when an uppercase query char must
+// match exactly.
+fn original_chars(query: &[char], choice: &Choice) -> Option<Vec<char>> {
+    if query.iter().any(char::is_ascii_uppercase) {
+        Some(choice.searchable.chars().collect())
+    } else {
+        None
+    }
+}
+
+// Lowercase query chars match either case, uppercase ones only themselves.
+fn smart_case_eq(nchar: char, choice: &Choice, original: &Option<Vec<char>>, index: usize) -> bool {
+    match original {
+        Some(chars) if nchar.is_ascii_uppercase() => chars[index] == nchar,
+        _ => choice.lower_searchable[index] == nchar,
+    }
+}
+
 impl Score {
-    pub fn new(query: &[char], choice: &Choice) -> Score {
+    pub fn new(query: &[char], choice: &Choice, profile: &ScoringProfile) -> Score {
         let query_length = query.len();
 
         if query_length == 0 {
             // empty needle
-            Score { score: MIN, positions: vec![] }
+            Score { score: MIN, positions: vec![], typos: 0 }
         } else if query_length == choice.searchable_len {
             // We only get here if we match so lengths match they
-            Score { score: MAX, positions: (0..query_length).collect() }
+            Score { score: MAX, positions: (0..query_length).collect(), typos: 0 }
+        } else if choice.approximate {
+            greedy(query, choice, query_length, choice.searchable_len, profile)
         } else {
-            let (main, diagonal) = compute(query, choice, query_length, choice.searchable_len);
+            let (main, diagonal) = compute(query, choice, query_length, choice.searchable_len, profile);
 
             Score {
                 score: main[(query_length - 1, choice.searchable_len - 1)],
-                positions: positions(choice.searchable_len, query_length, main, diagonal)
+                positions: positions(choice.searchable_len, query_length, main, diagonal, profile),
+                typos: 0,
             }
         }
     }
+
+    // Scores the query as a contiguous substring, picking the occurrence that
+    // scores best. Returns None if the query doesn't occur in the choice.
+    pub fn exact(query: &[char], choice: &Choice, profile: &ScoringProfile) -> Option<Score> {
+        let query_length = query.len();
+        let original = original_chars(query, choice);
+
+        if query_length == 0 {
+            return Some(Score { score: MIN, positions: vec![], typos: 0 });
+        } else if query_length > choice.searchable_len {
+            return None;
+        }
+
+        (0..=(choice.searchable_len - query_length))
+            .filter(|&start| {
+                query.iter().enumerate().all(|(i, &nchar)| smart_case_eq(nchar, choice, &original, start + i))
+            })
+            .map(|start| {
+                let positions: Vec<usize> = (start..(start + query_length)).collect();
+                let score = if query_length == choice.searchable_len {
+                    MAX
+                } else {
+                    score_positions(&positions, choice, choice.searchable_len, profile)
+                };
+
+                Score { score, positions, typos: 0 }
+            })
+            .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap())
+    }
+
+    // Drops the fewest query chars needed for the rest to match in order, up
+    // to max_typos of them, and scores what is left. Each dropped char covers
+    // an extra, mistyped or transposed char in the query.
+    pub fn typos(query: &[char], choice: &Choice, max_typos: usize, profile: &ScoringProfile) -> Option<Score> {
+        let query_length = query.len();
+        let original = original_chars(query, choice);
+
+        // How far into the choice the query read so far gets with each number
+        // of chars dropped, matching every kept char as early as possible.
+        // Only the previous row is needed, plus whether each char was dropped
+        // to walk back through the kept ones.
+        let mut reached: Vec<Option<usize>> = vec![None; max_typos + 1];
+        reached[0] = Some(0);
+        let mut dropped = vec![false; query_length * (max_typos + 1)];
+
+        for (i, &nchar) in query.iter().enumerate() {
+            let mut next = vec![None; max_typos + 1];
+
+            for typos in 0..=max_typos {
+                let kept = reached[typos].and_then(|from| {
+                    (from..choice.searchable_len).find(|&j| smart_case_eq(nchar, choice, &original, j)).map(|j| j + 1)
+                });
+                let skipped = if typos > 0 { reached[typos - 1] } else { None };
+
+                let (reach, drop) = match (kept, skipped) {
+                    (Some(kept), Some(skipped)) if skipped < kept => (Some(skipped), true),
+                    (None, Some(skipped)) => (Some(skipped), true),
+                    (kept, _) => (kept, false),
+                };
+                next[typos] = reach;
+                dropped[i * (max_typos + 1) + typos] = drop;
+            }
+
+            // More than max_typos chars would have to be dropped already.
+            if next.iter().all(Option::is_none) {
+                return None;
+            }
+            reached = next;
+        }
+
+        let typos = reached.iter().position(Option::is_some)?;
+        if typos == query_length {
+            return None;
+        }
+
+        let mut kept = Vec::with_capacity(query_length - typos);
+        let mut remaining = typos;
+        for i in (0..query_length).rev() {
+            if dropped[i * (max_typos + 1) + remaining] {
+                remaining -= 1;
+            } else {
+                kept.push(query[i]);
+            }
+        }
+        kept.reverse();
+
+        let mut score = Score::new(&kept, choice, profile);
+        score.typos = typos;
+
+        Some(score)
+    }
+
+    // Ranks earlier matches first, then shorter ones. Every span the regex
+    // matches is highlighted.
+    pub fn regex(regex: &Regex, choice: &Choice) -> Option<Score> {
+        let first = regex.find(&choice.searchable)?;
+        let start = choice.searchable[..first.start()].chars().count() as f32;
+        let length = first.as_str().chars().count() as f32;
+
+        let positions = regex
+            .find_iter(&choice.searchable)
+            .flat_map(|mat| {
+                let span_start = choice.searchable[..mat.start()].chars().count();
+
+                span_start..(span_start + mat.as_str().chars().count())
+            })
+            .collect();
+
+        Some(Score { score: -start - length / (length + 1.0), positions, typos: 0 })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::bonus;
     use crate::config::Config;
 
+    const GAP_LEADING: f32 = ScoringProfile::PATH.gap_leading;
+    const GAP_INNER: f32 = ScoringProfile::PATH.gap_inner;
+    const GAP_TRAILING: f32 = ScoringProfile::PATH.gap_trailing;
+    const MATCH_CONSECUTIVE: f32 = ScoringProfile::PATH.match_consecutive;
+    const FILENAME: f32 = ScoringProfile::PATH.filename;
+    const SLASH: f32 = ScoringProfile::PATH.slash;
+    const CAPITAL: f32 = ScoringProfile::PATH.capital;
+    const DOT: f32 = ScoringProfile::PATH.dot;
+
     #[test]
     fn prefer_starts_of_words_test() {
         assert!(score("amor", "app/models/order") > score("amor", "app/models/zrder"));
@@ -165,40 +352,127 @@ mod tests {
 
     #[test]
     fn score_gaps_test() {
-        assert_eq!(GAP_LEADING + bonus::FILENAME, score("a", "*a"));
-        assert_eq!(GAP_LEADING * 2.0 + bonus::FILENAME, score("a", "*ba"));
-        assert_eq!(GAP_LEADING * 2.0 + GAP_TRAILING + bonus::FILENAME, score("a", "**a*"));
-        assert_eq!(GAP_LEADING * 2.0 + GAP_TRAILING * 2.0 + bonus::FILENAME, score("a", "**a**"));
-        assert_eq!(GAP_LEADING * 2.0 + MATCH_CONSECUTIVE + GAP_TRAILING * 2.0 + bonus::FILENAME, score("aa", "**aa**"));
-        approx_eq!(f32, GAP_LEADING + GAP_LEADING + GAP_INNER + GAP_TRAILING + GAP_TRAILING + 2.0 * bonus::FILENAME, score("aa", "**a*a**"), epsilon = 0.001);
+        assert_eq!(GAP_LEADING + FILENAME, score("a", "*a"));
+        assert_eq!(GAP_LEADING * 2.0 + FILENAME, score("a", "*ba"));
+        assert_eq!(GAP_LEADING * 2.0 + GAP_TRAILING + FILENAME, score("a", "**a*"));
+        assert_eq!(GAP_LEADING * 2.0 + GAP_TRAILING * 2.0 + FILENAME, score("a", "**a**"));
+        assert_eq!(GAP_LEADING * 2.0 + MATCH_CONSECUTIVE + GAP_TRAILING * 2.0 + FILENAME, score("aa", "**aa**"));
+        approx_eq!(f32, GAP_LEADING + GAP_LEADING + GAP_INNER + GAP_TRAILING + GAP_TRAILING + 2.0 * FILENAME, score("aa", "**a*a**"), epsilon = 0.001);
     }
 
     #[test]
     fn score_consecutive_test() {
-        assert_eq!(GAP_LEADING + MATCH_CONSECUTIVE + bonus::FILENAME, score("aa", "*aa"));
-        assert_eq!(GAP_LEADING + MATCH_CONSECUTIVE * 2.0 + bonus::FILENAME, score("aaa", "*aaa"));
-        approx_eq!(f32, GAP_LEADING + GAP_INNER + MATCH_CONSECUTIVE + 2.0 * bonus::FILENAME, score("aaa", "*a*aa"), epsilon = 0.001);
+        assert_eq!(GAP_LEADING + MATCH_CONSECUTIVE + FILENAME, score("aa", "*aa"));
+        assert_eq!(GAP_LEADING + MATCH_CONSECUTIVE * 2.0 + FILENAME, score("aaa", "*aaa"));
+        approx_eq!(f32, GAP_LEADING + GAP_INNER + MATCH_CONSECUTIVE + 2.0 * FILENAME, score("aaa", "*a*aa"), epsilon = 0.001);
     }
 
     #[test]
     fn score_slash_test() {
-        assert_eq!(GAP_LEADING + bonus::SLASH + bonus::FILENAME, score("a", "/a"));
-        assert_eq!(GAP_LEADING * 2.0 + bonus::SLASH + bonus::FILENAME, score("a", "*/a"));
-        assert_eq!(GAP_LEADING * 2.0 + bonus::SLASH + MATCH_CONSECUTIVE + bonus::FILENAME, score("aa", "a/aa"));
+        assert_eq!(GAP_LEADING + SLASH + FILENAME, score("a", "/a"));
+        assert_eq!(GAP_LEADING * 2.0 + SLASH + FILENAME, score("a", "*/a"));
+        assert_eq!(GAP_LEADING * 2.0 + SLASH + MATCH_CONSECUTIVE + FILENAME, score("aa", "a/aa"));
     }
 
     #[test]
     fn score_capital_test() {
-        assert_eq!(GAP_LEADING + bonus::CAPITAL + bonus::FILENAME, score("a", "bA"));
-        assert_eq!(GAP_LEADING * 2.0 + bonus::CAPITAL + bonus::FILENAME, score("a", "baA"));
-        approx_eq!(f32, GAP_LEADING * 2.0 + bonus::CAPITAL + MATCH_CONSECUTIVE + bonus::FILENAME, score("aa", "baAa"), epsilon = 0.001);
+        assert_eq!(GAP_LEADING + CAPITAL + FILENAME, score("a", "bA"));
+        assert_eq!(GAP_LEADING * 2.0 + CAPITAL + FILENAME, score("a", "baA"));
+        approx_eq!(f32, GAP_LEADING * 2.0 + CAPITAL + MATCH_CONSECUTIVE + FILENAME, score("aa", "baAa"), epsilon = 0.001);
     }
 
     #[test]
     fn score_dot_test() {
-        assert_eq!(GAP_LEADING + bonus::DOT + bonus::FILENAME, score("a", ".a"));
-        assert_eq!(GAP_LEADING * 3.0 + bonus::DOT + bonus::FILENAME, score("a", "*a.a"));
-        assert_eq!(GAP_LEADING + GAP_INNER + bonus::DOT + bonus::FILENAME, score("a", "*a.a"));
+        assert_eq!(GAP_LEADING + DOT + FILENAME, score("a", ".a"));
+        assert_eq!(GAP_LEADING * 3.0 + DOT + FILENAME, score("a", "*a.a"));
+        assert_eq!(GAP_LEADING + GAP_INNER + DOT + FILENAME, score("a", "*a.a"));
+    }
+
+    #[test]
+    fn score_with_profile_test() {
+        assert!(score("l", "a_l") > score("l", "aaL"));
+        assert_eq!(score_with("l", "a_l", ScoringProfile::CODE), score_with("l", "aaL", ScoringProfile::CODE));
+        assert!(score_with("ab", "x/ab", ScoringProfile::PLAIN) < score("ab", "x/ab"));
+    }
+
+    #[test]
+    fn approximate_score_test() {
+        assert!(approx_eq!(f32, score("a", "**a**"), approximate_score("a", "**a**"), epsilon = 0.001));
+        assert!(approx_eq!(f32, score("aa", "*aa"), approximate_score("aa", "*aa"), epsilon = 0.001));
+        assert!(approx_eq!(f32, score("a", "*/a"), approximate_score("a", "*/a"), epsilon = 0.001));
+        assert!(approx_eq!(f32, score("aa", "**a*a**"), approximate_score("aa", "**a*a**"), epsilon = 0.001));
+        assert!(approximate_score("test", "tests") > approximate_score("test", "testing"));
+    }
+
+    #[test]
+    fn approximate_positions_test() {
+        let positions = approximate_positions("abc", "a/a/b/c/c");
+
+        assert_eq!(2, positions[0]);
+        assert_eq!(4, positions[1]);
+        assert_eq!(6, positions[2]);
+        assert_eq!(3, positions.len());
+    }
+
+    #[test]
+    fn exact_test() {
+        assert!(exact("abc", "a_b_c").is_none());
+        assert!(exact("Abc", "abc").is_none());
+        assert_eq!(vec![1, 2, 3], exact("Abc", "xAbc").unwrap().positions);
+        assert_eq!(MAX, exact("abc", "aBc").unwrap().score);
+        assert_eq!(MIN, exact("", "abc").unwrap().score);
+        assert!(exact("abcd", "abc").is_none());
+    }
+
+    #[test]
+    fn exact_prefers_word_starts_test() {
+        assert_eq!(vec![7, 8, 9], exact("con", "icon_a/con.rb").unwrap().positions);
+        assert!(exact("con", "app/con.rb").unwrap().score > exact("con", "app/icon.rb").unwrap().score);
+    }
+
+    #[test]
+    fn regex_test() {
+        let regex = Regex::new("o+").unwrap();
+        let choice = Choice::new("foo/boo", &config());
+        let score = Score::regex(&regex, &choice).unwrap();
+
+        assert_eq!(vec![1, 2, 5, 6], score.positions);
+        assert!(Score::regex(&regex, &Choice::new("bar", &config())).is_none());
+    }
+
+    #[test]
+    fn regex_prefers_earlier_and_shorter_test() {
+        let regex = Regex::new("a.*b").unwrap();
+        let earlier = Score::regex(&regex, &Choice::new("ab", &config())).unwrap();
+        let later = Score::regex(&regex, &Choice::new("xab", &config())).unwrap();
+        let longer = Score::regex(&regex, &Choice::new("axb", &config())).unwrap();
+
+        assert!(earlier.score > later.score);
+        assert!(earlier.score > longer.score);
+        assert!(longer.score > later.score);
+    }
+
+    #[test]
+    fn typos_test() {
+        assert_eq!(1, typos("contorller", "app/controllers", 1).unwrap().typos);
+        assert_eq!(1, typos("conxtroller", "app/controllers", 1).unwrap().typos);
+        assert_eq!(2, typos("cnotorller", "app/controllers", 2).unwrap().typos);
+        assert!(typos("cnotorller", "app/controllers", 1).is_none());
+        assert!(typos("x", "app/controllers", 1).is_none());
+        assert!(typos("Contorller", "app/controllers", 1).is_none());
+    }
+
+    #[test]
+    fn typos_approximate_test() {
+        let choice = Choice::new("app/controllers", &approximate_config());
+        let score = Score::typos(&"contorller".chars().collect::<Vec<char>>(), &choice, 1, &ScoringProfile::PATH);
+
+        assert_eq!(1, score.unwrap().typos);
+    }
+
+    #[test]
+    fn typos_positions_test() {
+        assert_eq!(vec![0, 1, 2], typos("fxoo", "foo", 1).unwrap().positions);
     }
 
     #[test]
@@ -284,7 +558,7 @@ mod tests {
         let choice = Choice::new("CODE_OF_CONDUCT.md", &config());
         let query = ['c', 'o', 'd', 'e'];
 
-        b.iter(|| compute(&query, &choice, 4, choice.searchable_len))
+        b.iter(|| compute(&query, &choice, 4, choice.searchable_len, &ScoringProfile::PATH))
     }
 
     #[bench]
@@ -292,7 +566,7 @@ mod tests {
         let choice = Choice::new("CODE_OF_CONDUCT.md", &config());
         let query = [];
 
-        b.iter(|| Score::new(&query, &choice))
+        b.iter(|| Score::new(&query, &choice, &ScoringProfile::PATH))
     }
 
     #[bench]
@@ -300,28 +574,71 @@ mod tests {
         let choice = Choice::new("gem", &config());
         let query = ['g', 'e', 'm'];
 
-        b.iter(|| Score::new(&query, &choice))
+        b.iter(|| Score::new(&query, &choice, &ScoringProfile::PATH))
     }
 
     fn score(choice: &str, query: &str) -> f32 {
         Score::new(
             &choice.chars().collect::<Vec<char>>(),
-            &Choice::new(query, &config())
+            &Choice::new(query, &config()),
+            &ScoringProfile::PATH
         ).score
     }
 
     fn positions(choice: &str, query: &str) -> Vec<usize> {
         Score::new(
             &choice.chars().collect::<Vec<char>>(),
-            &Choice::new(query, &config())
+            &Choice::new(query, &config()),
+            &ScoringProfile::PATH
+        ).positions
+    }
+
+    fn score_with(choice: &str, query: &str, profile: ScoringProfile) -> f32 {
+        Score::new(
+            &choice.chars().collect::<Vec<char>>(),
+            &Choice::new(query, &Config { profile, ..Config::default() }),
+            &profile
+        ).score
+    }
+
+    fn typos(choice: &str, query: &str, max_typos: usize) -> Option<Score> {
+        Score::typos(
+            &choice.chars().collect::<Vec<char>>(),
+            &Choice::new(query, &config()),
+            max_typos,
+            &ScoringProfile::PATH
+        )
+    }
+
+    fn exact(choice: &str, query: &str) -> Option<Score> {
+        Score::exact(
+            &choice.chars().collect::<Vec<char>>(),
+            &Choice::new(query, &config()),
+            &ScoringProfile::PATH
+        )
+    }
+
+    fn approximate_score(choice: &str, query: &str) -> f32 {
+        Score::new(
+            &choice.chars().collect::<Vec<char>>(),
+            &Choice::new(query, &approximate_config()),
+            &ScoringProfile::PATH
+        ).score
+    }
+
+    fn approximate_positions(choice: &str, query: &str) -> Vec<usize> {
+        Score::new(
+            &choice.chars().collect::<Vec<char>>(),
+            &Choice::new(query, &approximate_config()),
+            &ScoringProfile::PATH
         ).positions
     }
 
     fn config() -> Config {
-        Config {
-            lines: 10, prompt: ">".to_string(), show_scores: false,
-            query: None, delimiter: None, field: None, output: None,
-            benchmark: false,
-        }
+        Config::default()
+    }
+
+    fn approximate_config() -> Config {
+        Config { max_length: 0, ..Config::default() }
     }
 }
@@ -1,45 +1,105 @@
+use crate::color::Theme;
 use crate::cursor;
 use regex::Regex;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ViMode {
+    Insert,
+    Normal,
+}
+
+// A vi operator waiting for the motion that says what it applies to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Delete,
+    Change,
+}
 
 pub struct Search {
     pub query: Vec<char>,
     position: usize,
     prompt: String,
+    painted_prompt: String,
+    // Killed text, most recent last, which can be yanked back in.
+    kill_ring: Vec<Vec<char>>,
+    // Where the last yank was inserted and which kill it inserted.
+    yanked: Option<(usize, usize)>,
+    // The query and cursor before each edit, and before each undone one.
+    undo: Vec<(Vec<char>, usize)>,
+    redo: Vec<(Vec<char>, usize)>,
+    // Where the cursor ended up after inserting a character, so typing on
+    // from there adds to the same undo step.
+    typing_at: Option<usize>,
+    // Set with --vi. In normal mode the cursor sits on a character rather
+    // than between two, so it never goes past the last one.
+    vi: Option<ViMode>,
+    pending: Option<Operator>,
 }
 
 impl Search {
+    const KILL_RING_SIZE: usize = 16;
+    const UNDO_SIZE: usize = 100;
+
     pub fn new<S: Into<String>>(prompt: S) -> Search {
+        let prompt = prompt.into();
+
         Search {
             query: vec![],
             position: 0,
-            prompt: prompt.into(),
+            painted_prompt: prompt.clone(),
+            prompt,
+            kill_ring: vec![],
+            yanked: None,
+            undo: vec![],
+            redo: vec![],
+            typing_at: None,
+            vi: None,
+            pending: None,
         }
     }
 
+    pub fn paint_prompt(&mut self, theme: &Theme) {
+        self.painted_prompt = theme.prompt(&self.prompt);
+    }
+
     pub fn draw(&self) -> String {
         let query: String = self.query.iter().collect();
-        let current_col = self.prompt.chars().count() + self.position + 1;
+        let before: String = self.query.iter().take(self.position).collect();
+        let indicator = match self.vi {
+            Some(ViMode::Insert) => "[I] ",
+            Some(ViMode::Normal) => "[N] ",
+            None => "",
+        };
+        let current_col = indicator.width() + self.prompt.width() + before.width() + 1;
 
         format!(
-            "{}\r{}{}{}",
+            "{}\r{}{}{}{}",
             cursor::clear_line(),
-            self.prompt,
+            indicator,
+            self.painted_prompt,
             query,
             cursor::col(current_col)
         )
     }
 
     pub fn keypress(&mut self, character: char) -> String {
+        let before = self.snapshot();
+        let typing = self.typing_at == Some(self.position);
         self.query.insert(self.position, character);
         self.position += 1;
+        self.record(before, typing);
+        self.typing_at = Some(self.position);
 
         self.draw()
     }
 
     pub fn backspace(&mut self) -> Option<String> {
         if self.position > 0 {
+            let before = self.snapshot();
             self.position -= 1;
             self.query.remove(self.position);
+            self.record(before, false);
             Some(self.draw())
         } else {
             None
@@ -48,37 +108,367 @@ impl Search {
 
     pub fn delete(&mut self) -> Option<String> {
         if self.position != self.query.len() {
+            let before = self.snapshot();
             self.query.remove(self.position);
+            self.record(before, false);
+            self.clamp();
             Some(self.draw())
         } else {
             None
         }
     }
 
-    pub fn left(&mut self) -> Option<&str> {
+    // Moves over a character by as many columns as it takes up, which is
+    // none for combining characters and two for wide ones.
+    pub fn left(&mut self) -> Option<String> {
         if self.position > 0 {
             self.position -= 1;
-            Some(cursor::left())
+            self.typing_at = None;
+            Some(match self.query[self.position].width().unwrap_or(0) {
+                0 => String::new(),
+                width => cursor::left(width),
+            })
         } else {
             None
         }
     }
 
-    pub fn right(&mut self) -> Option<&str> {
-        if self.query.len() != self.position {
+    pub fn right(&mut self) -> Option<String> {
+        if self.position < self.end() {
             self.position += 1;
-            Some(cursor::right())
+            self.typing_at = None;
+            Some(match self.query[self.position - 1].width().unwrap_or(0) {
+                0 => String::new(),
+                width => cursor::right(width),
+            })
         } else {
             None
         }
     }
 
     pub fn clear(&mut self) -> String {
+        let before = self.snapshot();
         self.query = vec![];
         self.position = 0;
+        self.record(before, false);
+
+        self.draw()
+    }
+
+    pub fn beginning_of_line(&mut self) -> String {
+        self.position = 0;
+        self.typing_at = None;
+        self.draw()
+    }
+
+    pub fn end_of_line(&mut self) -> String {
+        self.position = self.end();
+        self.typing_at = None;
+        self.draw()
+    }
+
+    // Kills from the cursor to the end of the line.
+    pub fn kill_line(&mut self) -> String {
+        let before = self.snapshot();
+        let killed = self.query.drain(self.position..).collect();
+        self.kill(killed);
+        self.record(before, false);
+        self.clamp();
+
+        self.draw()
+    }
+
+    // Kills from the start of the line to the cursor.
+    pub fn unix_line_discard(&mut self) -> String {
+        let before = self.snapshot();
+        let killed = self.query.drain(..self.position).collect();
+        self.position = 0;
+        self.kill(killed);
+        self.record(before, false);
+
+        self.draw()
+    }
+
+    // Kills the word before the cursor, words being anything between whitespace.
+    pub fn unix_word_rubout(&mut self) -> String {
+        let before = self.snapshot();
+        let word_end = self.query[..self.position].iter().rposition(|c| !c.is_whitespace()).map_or(0, |i| i + 1);
+        let word_start = self.query[..word_end].iter().rposition(|c| c.is_whitespace()).map_or(0, |i| i + 1);
+
+        let killed = self.query.drain(word_start..self.position).collect();
+        self.position = word_start;
+        self.kill(killed);
+        self.record(before, false);
+
+        self.draw()
+    }
+
+    // Inserts the most recently killed text at the cursor.
+    pub fn yank(&mut self) -> Option<String> {
+        let index = self.kill_ring.len().checked_sub(1)?;
+        let before = self.snapshot();
+        self.insert_kill(index);
+        self.record(before, false);
+
+        Some(self.draw())
+    }
+
+    // Swaps the text that was just yanked for the kill before it.
+    pub fn yank_pop(&mut self) -> Option<String> {
+        let (start, index) = self.yanked?;
+        let yanked = &self.kill_ring[index];
+        if self.position < start || self.query[start..self.position] != yanked[..] {
+            return None;
+        }
+
+        let before = self.snapshot();
+        self.query.drain(start..self.position);
+        self.position = start;
+        self.insert_kill((index + self.kill_ring.len() - 1) % self.kill_ring.len());
+        self.record(before, false);
+
+        Some(self.draw())
+    }
+
+    pub fn undo(&mut self) -> Option<String> {
+        let (query, position) = self.undo.pop()?;
+        self.redo.push(self.snapshot());
+        self.restore(query, position);
+
+        Some(self.draw())
+    }
+
+    pub fn redo(&mut self) -> Option<String> {
+        let (query, position) = self.redo.pop()?;
+        self.undo.push(self.snapshot());
+        self.restore(query, position);
+
+        Some(self.draw())
+    }
+
+    pub fn enable_vi(&mut self) {
+        self.vi = Some(ViMode::Insert);
+    }
+
+    pub fn vi_mode(&self) -> Option<ViMode> {
+        self.vi
+    }
+
+    // Leaves insert mode with the cursor on the character before it, like vi.
+    pub fn normal_mode(&mut self) -> String {
+        if self.vi == Some(ViMode::Insert) {
+            self.position = self.position.saturating_sub(1);
+        }
+        self.vi = Some(ViMode::Normal);
+        self.pending = None;
+        self.typing_at = None;
+
+        self.draw()
+    }
+
+    pub fn insert(&mut self) -> String {
+        self.insert_at(self.position)
+    }
+
+    pub fn append(&mut self) -> String {
+        self.insert_at((self.position + 1).min(self.query.len()))
+    }
+
+    pub fn insert_at_start(&mut self) -> String {
+        self.insert_at(0)
+    }
+
+    pub fn append_at_end(&mut self) -> String {
+        self.insert_at(self.query.len())
+    }
+
+    // Kills from the cursor to the end of the line and starts inserting.
+    pub fn change_to_end(&mut self) -> String {
+        self.kill_line();
+        self.append_at_end()
+    }
+
+    // Waits for a motion to delete or change up to. Given twice, like `dd`,
+    // the operator applies to the whole line.
+    pub fn operator(&mut self, operator: Operator) -> Option<String> {
+        if self.pending.take() != Some(operator) {
+            self.pending = Some(operator);
+            return None;
+        }
+
+        self.position = 0;
+        self.pending = Some(operator);
+        Some(self.motion(self.query.len(), false).0)
+    }
+
+    pub fn cancel_operator(&mut self) {
+        self.pending = None;
+    }
+
+    // Moves to the start of the next vi word, where a word is either a run of
+    // letters, digits and underscores or a run of other non-blank characters.
+    // With `c` pending it changes to the end of the current word instead,
+    // like vi's `cw`.
+    pub fn vi_forward_word(&mut self) -> (String, bool) {
+        if self.pending == Some(Operator::Change) && self.query.get(self.position).is_some_and(|&c| class(c) != 0) {
+            let end = self.current_word_end(self.position);
+            return self.motion(end, true);
+        }
+
+        let mut target = self.position;
+        if let Some(&c) = self.query.get(target) {
+            let start_class = class(c);
+            while start_class != 0 && self.query.get(target).is_some_and(|&c| class(c) == start_class) {
+                target += 1;
+            }
+        }
+        while self.query.get(target).is_some_and(|&c| class(c) == 0) {
+            target += 1;
+        }
+
+        self.motion(target, false)
+    }
+
+    pub fn vi_backward_word(&mut self) -> (String, bool) {
+        let mut target = self.position;
+        while target > 0 && class(self.query[target - 1]) == 0 {
+            target -= 1;
+        }
+        if target > 0 {
+            let word_class = class(self.query[target - 1]);
+            while target > 0 && class(self.query[target - 1]) == word_class {
+                target -= 1;
+            }
+        }
+
+        self.motion(target, false)
+    }
+
+    pub fn vi_end_of_word(&mut self) -> (String, bool) {
+        let mut target = self.position + 1;
+        while self.query.get(target).is_some_and(|&c| class(c) == 0) {
+            target += 1;
+        }
+        let target = match self.query.get(target) {
+            Some(_) => self.current_word_end(target),
+            None => self.query.len().saturating_sub(1),
+        };
+
+        self.motion(target, true)
+    }
+
+    // The last character of the word at `from`.
+    fn current_word_end(&self, from: usize) -> usize {
+        let word_class = class(self.query[from]);
+        let mut end = from;
+        while self.query.get(end + 1).is_some_and(|&c| class(c) == word_class) {
+            end += 1;
+        }
+
+        end
+    }
+
+    // Moves to `target`, or applies the pending operator between the cursor
+    // and `target`, which is included for motions like `e`. Also returns
+    // whether the query changed.
+    fn motion(&mut self, target: usize, inclusive: bool) -> (String, bool) {
+        let operator = match self.pending.take() {
+            Some(operator) => operator,
+            None => {
+                self.position = target;
+                self.typing_at = None;
+                self.clamp();
+                return (self.draw(), false);
+            }
+        };
+
+        let start = self.position.min(target);
+        let end = (self.position.max(target) + usize::from(inclusive)).min(self.query.len());
+        let before = self.snapshot();
+        let killed = self.query.drain(start..end).collect();
+        self.position = start;
+        self.kill(killed);
+        self.record(before, false);
+
+        let drawn = match operator {
+            Operator::Delete => {
+                self.clamp();
+                self.draw()
+            }
+            Operator::Change => self.insert_at(start),
+        };
+
+        (drawn, start < end)
+    }
+
+    fn insert_at(&mut self, position: usize) -> String {
+        self.vi = Some(ViMode::Insert);
+        self.position = position;
+        self.typing_at = None;
+
         self.draw()
     }
 
+    // How far right the cursor can go.
+    fn end(&self) -> usize {
+        match self.vi {
+            Some(ViMode::Normal) => self.query.len().saturating_sub(1),
+            _ => self.query.len(),
+        }
+    }
+
+    fn clamp(&mut self) {
+        self.position = self.position.min(self.end());
+    }
+
+    fn snapshot(&self) -> (Vec<char>, usize) {
+        (self.query.clone(), self.position)
+    }
+
+    fn restore(&mut self, query: Vec<char>, position: usize) {
+        self.query = query;
+        self.position = position;
+        self.typing_at = None;
+        self.clamp();
+    }
+
+    // Keeps what the query was before an edit so it can be undone, unless the
+    // edit didn't change anything or continues the step before it.
+    fn record(&mut self, before: (Vec<char>, usize), continues: bool) {
+        self.typing_at = None;
+        if before.0 == self.query {
+            return;
+        }
+
+        if !continues {
+            if self.undo.len() == Self::UNDO_SIZE {
+                self.undo.remove(0);
+            }
+            self.undo.push(before);
+        }
+        self.redo.clear();
+    }
+
+    fn kill(&mut self, killed: Vec<char>) {
+        if killed.is_empty() {
+            return;
+        }
+
+        if self.kill_ring.len() == Self::KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+        self.kill_ring.push(killed);
+    }
+
+    fn insert_kill(&mut self, index: usize) {
+        let start = self.position;
+        let text = self.kill_ring[index].clone();
+
+        self.position += text.len();
+        self.query.splice(start..start, text);
+        self.yanked = Some((start, index));
+    }
+
     pub fn left_word(&mut self) -> String {
         let regex = Regex::new(r"\b\w").unwrap();
         let mut new_position = self.position;
@@ -91,6 +481,7 @@ impl Search {
                 }
             });
         self.position = new_position;
+        self.typing_at = None;
 
         self.draw()
     }
@@ -111,6 +502,7 @@ impl Search {
         } else {
             self.position = new_position;
         }
+        self.typing_at = None;
 
         self.draw()
     }
@@ -127,10 +519,11 @@ impl Search {
                 }
             });
 
-        (new_position..self.position).rev().for_each(|i| {
-            self.position -= 1;
-            self.query.remove(i);
-        });
+        let before = self.snapshot();
+        let killed = self.query.drain(new_position..self.position).collect();
+        self.position = new_position;
+        self.kill(killed);
+        self.record(before, false);
 
         self.draw()
     }
@@ -149,21 +542,36 @@ impl Search {
         if self.position == new_position {
             new_position = self.query.len();
         }
-        (self.position..new_position).for_each(|_i| {
-            self.query.remove(self.position);
-        });
+        let before = self.snapshot();
+        let killed = self.query.drain(self.position..new_position).collect();
+        self.kill(killed);
+        self.record(before, false);
 
         self.draw()
     }
 
     pub fn set_query(&mut self, new_query: &str) -> String {
+        let before = self.snapshot();
         self.query = new_query.chars().collect::<Vec<char>>();
-        self.position = new_query.len();
+        self.position = self.query.len();
+        self.record(before, false);
+        self.clamp();
 
         self.draw()
     }
 }
 
+// Vi's character classes: blanks, word characters and everything else.
+fn class(c: char) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,6 +586,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_draw_painted_prompt() {
+        let mut search = Search::new("> ");
+        search.paint_prompt(&Theme::light());
+
+        assert_eq!(
+            format!("{}\r\x1B[34m> \x1B[39m{}", cursor::clear_line(), cursor::col(3)),
+            search.draw()
+        );
+    }
+
     #[test]
     fn test_keypress() {
         let mut search = Search::new("> ");
@@ -241,10 +660,30 @@ mod tests {
         search.query = vec!['a', 'b', 'c'];
         search.position = 1;
 
-        assert_eq!(cursor::left().to_string(), search.left().unwrap());
+        assert_eq!(cursor::left(1), search.left().unwrap());
         assert_eq!(0, search.position);
     }
 
+    #[test]
+    fn test_left_wide() {
+        let mut search = Search::new("> ");
+        search.query = vec!['日', 'e', '\u{301}'];
+        search.position = 3;
+
+        assert_eq!("", search.left().unwrap());
+        assert_eq!(cursor::left(1), search.left().unwrap());
+        assert_eq!(cursor::left(2), search.left().unwrap());
+    }
+
+    #[test]
+    fn test_draw_wide() {
+        let mut search = Search::new("❯ ");
+        search.query = vec!['日', '本'];
+        search.position = 1;
+
+        assert_eq!(format!("{}\r❯ 日本{}", cursor::clear_line(), cursor::col(5)), search.draw());
+    }
+
     #[test]
     fn test_left_none() {
         let mut search = Search::new("> ");
@@ -261,7 +700,7 @@ mod tests {
         search.query = vec!['a', 'b', 'c'];
         search.position = 1;
 
-        assert_eq!(cursor::right().to_string(), search.right().unwrap());
+        assert_eq!(cursor::right(1), search.right().unwrap());
         assert_eq!(2, search.position);
     }
 
@@ -366,6 +805,245 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_line_motions() {
+        let mut search = Search::new("> ");
+        search.query = vec!['a', 'b', 'c'];
+        search.position = 1;
+
+        assert_eq!(format!("{}\r> abc{}", cursor::clear_line(), cursor::col(6)), search.end_of_line());
+        assert_eq!(format!("{}\r> abc{}", cursor::clear_line(), cursor::col(3)), search.beginning_of_line());
+    }
+
+    #[test]
+    fn test_kill_line_and_yank() {
+        let mut search = Search::new("> ");
+        search.query = vec!['a', 'b', 'c'];
+        search.position = 1;
+
+        search.kill_line();
+        assert_eq!(vec!['a'], search.query);
+
+        search.beginning_of_line();
+        search.yank();
+        assert_eq!(vec!['b', 'c', 'a'], search.query);
+        assert_eq!(2, search.position);
+    }
+
+    #[test]
+    fn test_unix_line_discard() {
+        let mut search = Search::new("> ");
+        search.query = vec!['a', 'b', 'c'];
+        search.position = 2;
+
+        search.unix_line_discard();
+        assert_eq!(vec!['c'], search.query);
+        assert_eq!(0, search.position);
+        assert_eq!(vec![vec!['a', 'b']], search.kill_ring);
+    }
+
+    #[test]
+    fn test_unix_word_rubout() {
+        let mut search = Search::new("> ");
+        search.query = "foo/bar baz  ".chars().collect();
+        search.position = 13;
+
+        search.unix_word_rubout();
+        assert_eq!("foo/bar ", search.query.iter().collect::<String>());
+
+        search.unix_word_rubout();
+        assert_eq!("", search.query.iter().collect::<String>());
+        assert_eq!(0, search.position);
+    }
+
+    #[test]
+    fn test_yank_pop() {
+        let mut search = Search::new("> ");
+        search.query = "one two".chars().collect();
+        search.position = 7;
+        search.unix_word_rubout();
+        search.unix_word_rubout();
+
+        assert!(search.yank_pop().is_none());
+        search.yank();
+        assert_eq!("one ", search.query.iter().collect::<String>());
+        search.yank_pop();
+        assert_eq!("two", search.query.iter().collect::<String>());
+        search.yank_pop();
+        assert_eq!("one ", search.query.iter().collect::<String>());
+        assert_eq!(4, search.position);
+    }
+
+    #[test]
+    fn test_yank_empty() {
+        let mut search = Search::new("> ");
+
+        assert!(search.yank().is_none());
+    }
+
+    #[test]
+    fn test_undo_merges_typing() {
+        let mut search = Search::new("> ");
+        "ab".chars().for_each(|c| { search.keypress(c); });
+        search.backspace();
+        search.keypress('c');
+
+        search.undo();
+        assert_eq!(vec!['a'], search.query);
+        search.undo();
+        assert_eq!(vec!['a', 'b'], search.query);
+        search.undo();
+        assert!(search.query.is_empty());
+        assert!(search.undo().is_none());
+    }
+
+    #[test]
+    fn test_undo_moving_starts_a_new_step() {
+        let mut search = Search::new("> ");
+        search.keypress('a');
+        search.left();
+        search.keypress('b');
+
+        search.undo();
+        assert_eq!(vec!['a'], search.query);
+        assert_eq!(0, search.position);
+    }
+
+    #[test]
+    fn test_undo_moving_back_starts_a_new_step() {
+        let mut search = Search::new("> ");
+        "ab".chars().for_each(|c| { search.keypress(c); });
+        search.left();
+        search.right();
+        search.keypress('c');
+
+        search.undo();
+        assert_eq!(vec!['a', 'b'], search.query);
+
+        let mut search = Search::new("> ");
+        "ab".chars().for_each(|c| { search.keypress(c); });
+        search.beginning_of_line();
+        search.end_of_line();
+        search.keypress('c');
+
+        search.undo();
+        assert_eq!(vec!['a', 'b'], search.query);
+    }
+
+    #[test]
+    fn test_redo() {
+        let mut search = Search::new("> ");
+        search.keypress('a');
+        search.set_query("app/models/order.rb");
+
+        search.undo();
+        assert_eq!(vec!['a'], search.query);
+        assert_eq!(1, search.position);
+        search.redo();
+        assert_eq!("app/models/order.rb", search.query.iter().collect::<String>());
+        assert!(search.redo().is_none());
+
+        search.undo();
+        search.keypress('b');
+        assert!(search.redo().is_none());
+    }
+
+    #[test]
+    fn test_vi_modes() {
+        let mut search = Search::new("> ");
+        search.enable_vi();
+        search.set_query("ab");
+
+        assert_eq!(format!("{}\r[N] > ab{}", cursor::clear_line(), cursor::col(8)), search.normal_mode());
+        assert_eq!(1, search.position);
+        search.end_of_line();
+        assert_eq!(1, search.position);
+        assert!(search.right().is_none());
+
+        assert_eq!(format!("{}\r[I] > ab{}", cursor::clear_line(), cursor::col(9)), search.append());
+        assert_eq!(2, search.position);
+
+        search.normal_mode();
+        search.set_query("abc");
+        assert_eq!(2, search.position);
+    }
+
+    #[test]
+    fn test_vi_word_motions() {
+        let mut search = Search::new("> ");
+        search.enable_vi();
+        search.set_query("app/models  order.rb");
+        search.normal_mode();
+        search.beginning_of_line();
+
+        assert!(!search.vi_forward_word().1);
+        assert_eq!(3, search.position);
+        search.vi_forward_word();
+        assert_eq!(4, search.position);
+        search.vi_forward_word();
+        assert_eq!(12, search.position);
+        search.vi_end_of_word();
+        assert_eq!(16, search.position);
+        search.vi_backward_word();
+        assert_eq!(12, search.position);
+        search.vi_backward_word();
+        assert_eq!(4, search.position);
+    }
+
+    #[test]
+    fn test_vi_delete_word() {
+        let mut search = Search::new("> ");
+        search.enable_vi();
+        search.set_query("foo bar baz");
+        search.normal_mode();
+        search.beginning_of_line();
+
+        search.operator(Operator::Delete);
+        assert!(search.vi_forward_word().1);
+        assert_eq!("bar baz", search.query.iter().collect::<String>());
+        assert_eq!(Some(ViMode::Normal), search.vi_mode());
+
+        search.end_of_line();
+        search.operator(Operator::Delete);
+        search.vi_forward_word();
+        assert_eq!("bar ba", search.query.iter().collect::<String>());
+        assert_eq!(5, search.position);
+    }
+
+    #[test]
+    fn test_vi_change_word() {
+        let mut search = Search::new("> ");
+        search.enable_vi();
+        search.set_query("foo bar");
+        search.normal_mode();
+        search.beginning_of_line();
+
+        search.operator(Operator::Change);
+        search.vi_forward_word();
+        assert_eq!(" bar", search.query.iter().collect::<String>());
+        assert_eq!(Some(ViMode::Insert), search.vi_mode());
+        assert_eq!(0, search.position);
+
+        search.normal_mode();
+        assert!(search.operator(Operator::Delete).is_none());
+        assert!(search.operator(Operator::Delete).is_some());
+        assert!(search.query.is_empty());
+    }
+
+    #[test]
+    fn test_vi_change_to_end() {
+        let mut search = Search::new("> ");
+        search.enable_vi();
+        search.set_query("foo bar");
+        search.normal_mode();
+        search.vi_backward_word();
+
+        search.change_to_end();
+        assert_eq!("foo ", search.query.iter().collect::<String>());
+        assert_eq!(4, search.position);
+        assert_eq!(Some(ViMode::Insert), search.vi_mode());
+    }
+
     #[test]
     fn set_query_test() {
         let mut search = Search::new("> ");
@@ -1,13 +1,49 @@
-use raw_tty::IntoRawMode;
+use raw_tty::TtyModeGuard;
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fs::{self, File};
-use std::io::Write;
-use termion::input::TermRead;
+use std::io::{self, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::sync::{Arc, Mutex};
+use crate::cursor;
+use crate::events::{Event, Events};
+use std::sync::mpsc::Sender;
+use terminal_size::{terminal_size_using_fd, Height, Width};
 
 pub struct Terminal {
+    // Dropped before output, the guard needs the file to still be open.
+    raw_mode: Option<TtyModeGuard>,
+    mouse: bool,
+    // Keys typed while waiting for a position report, to be read as input.
+    unread: Arc<Mutex<VecDeque<u8>>>,
+    // The size of the terminal when the cursor row was last asked for.
+    reported_size: Option<(Width, Height)>,
     pub output: File,
 }
 
+// Reads the keys cursor_row read past before reading the terminal itself.
+struct Input {
+    unread: Arc<Mutex<VecDeque<u8>>>,
+    tty: File,
+}
+
+impl Read for Input {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut unread = self.unread.lock().unwrap();
+        if unread.is_empty() {
+            drop(unread);
+            return self.tty.read(buffer);
+        }
+
+        let length = buffer.len().min(unread.len());
+        for (slot, byte) in buffer.iter_mut().zip(unread.drain(..length)) {
+            *slot = byte;
+        }
+
+        Ok(length)
+    }
+}
+
 impl Terminal {
     pub fn new() -> Result<Terminal, Box<dyn Error>> {
         let output = fs::OpenOptions::new()
@@ -15,13 +51,88 @@ impl Terminal {
             .write(true)
             .open("/dev/tty")?;
 
-        Ok(Terminal { output })
+        Ok(Terminal { raw_mode: None, mouse: false, unread: Arc::default(), reported_size: None, output })
+    }
+
+    // Switches to raw mode until the terminal is dropped and starts reading keys.
+    pub fn events(&mut self) -> Result<(Events, Sender<Event>), Box<dyn Error>> {
+        self.resume_raw_mode()?;
+
+        Ok(Events::new(Input { unread: self.unread.clone(), tty: self.output.try_clone()? }))
+    }
+
+    // Hands the terminal back in the mode it was in before raw mode, so other
+    // programs can be run on it.
+    pub fn suspend_raw_mode(&mut self) {
+        if self.mouse {
+            self.print(cursor::disable_mouse());
+        }
+        self.raw_mode = None;
+    }
+
+    pub fn resume_raw_mode(&mut self) -> io::Result<()> {
+        let mut guard = TtyModeGuard::new(self.output.as_raw_fd())?;
+        guard.set_raw_mode()?;
+        self.raw_mode = Some(guard);
+        if self.mouse {
+            self.print(cursor::enable_mouse());
+        }
+
+        Ok(())
     }
 
-    pub fn keys(
-        &mut self,
-    ) -> Result<termion::input::Keys<raw_tty::RawReader<File>>, Box<dyn Error>> {
-        Ok(self.output.try_clone()?.into_raw_mode()?.keys())
+    // Turns on mouse reporting until the terminal is dropped.
+    pub fn enable_mouse(&mut self) {
+        self.mouse = true;
+        self.print(cursor::enable_mouse());
+    }
+
+    // Asks the terminal which row the cursor is on, counting from 1. Only
+    // works in raw mode while nothing else is reading keys, and gives up
+    // after a moment if the terminal doesn't answer. Keys typed before the
+    // answer arrives are kept to be read as input afterwards.
+    pub fn cursor_row(&mut self) -> io::Result<Option<usize>> {
+        self.reported_size = terminal_size_using_fd(self.output.as_raw_fd());
+
+        let guard = match self.raw_mode.as_mut() {
+            Some(guard) => guard,
+            None => return Ok(None),
+        };
+        guard.modify_mode(|mut ios| {
+            ios.c_lflag &= !(libc::ICANON | libc::ECHO);
+            ios.c_cc[libc::VMIN] = 0;
+            ios.c_cc[libc::VTIME] = 1;
+            ios
+        })?;
+
+        self.print(cursor::report_position());
+        let mut read = vec![];
+        let mut row = None;
+        let mut byte = [0];
+        while self.output.read(&mut byte)? == 1 {
+            read.push(byte[0]);
+            if byte[0] != b'R' {
+                continue;
+            }
+            if let Some(start) = cursor::find_report(&read) {
+                row = cursor::parse_row(&String::from_utf8_lossy(&read[start..]));
+                read.truncate(start);
+                break;
+            }
+        }
+        self.unread.lock().unwrap().extend(read);
+
+        if let Some(guard) = self.raw_mode.as_mut() {
+            guard.set_raw_mode()?;
+        }
+
+        Ok(row)
+    }
+
+    // Whether the terminal has changed size since the cursor row was last
+    // asked for, which can move everything on screen.
+    pub fn resized(&self) -> bool {
+        terminal_size_using_fd(self.output.as_raw_fd()) != self.reported_size
     }
 
     pub fn print(&mut self, text: &str) {
@@ -29,3 +140,13 @@ impl Terminal {
         self.output.flush().unwrap();
     }
 }
+
+// Runs on the way out of a panic too, as the stack unwinds.
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.mouse {
+            let _ = self.output.write_all(cursor::disable_mouse().as_bytes());
+            let _ = self.output.flush();
+        }
+    }
+}
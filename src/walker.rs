@@ -0,0 +1,42 @@
+use crate::config::WalkerOptions;
+use crate::events::{self, Event};
+use ignore::{WalkBuilder, WalkState};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+// Walks the current directory in parallel, respecting .gitignore and friends.
+pub fn spawn(options: WalkerOptions, events: Sender<Event>) {
+    let (paths, found) = mpsc::channel::<String>();
+
+    thread::spawn(move || {
+        WalkBuilder::new(".")
+            .hidden(!options.hidden)
+            .follow_links(options.follow)
+            .build_parallel()
+            .run(|| {
+                let paths = paths.clone();
+
+                Box::new(move |entry| {
+                    let entry = match entry {
+                        Ok(entry) if entry.depth() > 0 => entry,
+                        _ => return WalkState::Continue,
+                    };
+                    if entry.file_name() == ".git" {
+                        return WalkState::Skip;
+                    }
+                    let is_dir = entry.file_type().is_some_and(|file_type| file_type.is_dir());
+
+                    if (is_dir && options.dirs) || (!is_dir && options.files) {
+                        let path = entry.path().strip_prefix(".").unwrap_or(entry.path());
+                        if paths.send(path.to_string_lossy().into_owned()).is_err() {
+                            return WalkState::Quit;
+                        }
+                    }
+
+                    WalkState::Continue
+                })
+            });
+    });
+
+    events::batch(found, 0, events);
+}